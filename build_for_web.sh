@@ -1,5 +1,6 @@
 cargo build --profile wasm-release --target wasm32-unknown-unknown && \
 wasm-bindgen --out-dir ./webapp/ --target web --no-typescript target/wasm32-unknown-unknown/wasm-release/yogamat.wasm && \
+cp out_db webapp/ && \
 cd webapp && \
 wasm-opt -Oz -o yogamat_bg.wasm yogamat_bg.wasm && \
 echo "ADD to  yogamat.js init function"
@@ -0,0 +1,184 @@
+//! Whether a pose balances: the whole body's centre of mass against the
+//! area it stands on.
+//!
+//! Each segment of the body carries the share of the body's mass that
+//! anthropometric tables give it (Winter, Biomechanics and Motor Control of
+//! Human Movement), spread over its bones by volume. A body with bigger or
+//! smaller segments than the default one gets heavier or lighter segments in
+//! proportion to their volume. A bone's mass sits in the middle of its cube.
+//!
+//! The support polygon is the convex hull, seen from above, of the bone
+//! corners at the bottom of the body, where it touches the mat once it's
+//! grounded and would touch it if it isn't. The stability margin is how far
+//! the centre of mass is inside it, negative when it's outside and the pose
+//! would tip.
+
+use bevy::{
+    math::{Mat4, Vec2, Vec3},
+    transform::components::Transform,
+};
+
+use crate::{
+    kinematics::PosedSkeleton,
+    skeleton::{self, Segment, SkeletonBone},
+};
+
+/// Corners this close to the lowest one, in skeleton units, touch the
+/// floor. The bones are blocks, so a lying back only nears the floor in
+/// places, and the bottom of the calf stands in for the heel the foot
+/// doesn't have.
+const CONTACT: f32 = 3.0;
+
+/// Share of the body's mass in each segment, both sides together. The
+/// clavicles get a little of the trunk.
+pub fn segment_mass(segment: Segment) -> f32 {
+    match segment {
+        Segment::Head => 0.069,
+        Segment::Neck => 0.012,
+        Segment::Torso => 0.345,
+        Segment::Shoulder => 0.010,
+        Segment::Pelvis => 0.142,
+        Segment::UpperArm => 0.056,
+        Segment::Forearm => 0.032,
+        Segment::Hand => 0.012,
+        Segment::Thigh => 0.200,
+        Segment::Shin => 0.093,
+        Segment::Foot => 0.029,
+    }
+}
+
+fn segment_volume(bones: &[SkeletonBone], segment: Segment) -> f32 {
+    bones
+        .iter()
+        .filter(|bone| bone.segment == segment)
+        .map(|bone| bone.cube.volume())
+        .sum()
+}
+
+/// Mass of each of `bones` as a share of the whole body, in the same order.
+/// They add up to 1.
+pub fn bone_masses(bones: &[SkeletonBone]) -> Vec<f32> {
+    let default_bones = skeleton::bones();
+    let masses = bones
+        .iter()
+        .map(|bone| {
+            let default_volume = segment_volume(default_bones, bone.segment);
+            if default_volume > 0.0 {
+                segment_mass(bone.segment) * bone.cube.volume() / default_volume
+            } else {
+                0.0
+            }
+        })
+        .collect::<Vec<f32>>();
+    let total = masses.iter().sum::<f32>();
+    masses.into_iter().map(|mass| mass / total).collect()
+}
+
+/// Centre of mass of `bones` placed by `posed` and then by `root`.
+pub fn centre_of_mass(bones: &[SkeletonBone], posed: &PosedSkeleton, root: Mat4) -> Vec3 {
+    bones
+        .iter()
+        .zip(bone_masses(bones))
+        .filter_map(|(bone, mass)| {
+            let global = root * posed.global(bone.joint_id)?;
+            Some(global.transform_point3(bone.cube.transform.translation) * mass)
+        })
+        .sum()
+}
+
+/// Convex hull of `points`, counter-clockwise seen from above (+y) when
+/// x and y of the `Vec2`s are world x and z.
+pub fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    // Andrew's monotone chain. Seen from above with z pointing at the
+    // viewer's bottom, z × x is up, so clockwise in (x, z) is
+    // counter-clockwise from above.
+    let turn = |o: Vec2, a: Vec2, b: Vec2| (a - o).perp_dot(b - o);
+    let mut hull: Vec<Vec2> = Vec::new();
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], point) >= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Distance from `point` to the segment from `a` to `b`.
+fn segment_distance(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let along = b - a;
+    let t = if along.length_squared() > 0.0 {
+        ((point - a).dot(along) / along.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + along * t)
+}
+
+/// How far `point` is inside `polygon`, negative outside it. A polygon of
+/// one or two points has no inside.
+pub fn margin(point: Vec2, polygon: &[Vec2]) -> Option<f32> {
+    let edges = (0..polygon.len()).map(|i| (polygon[i], polygon[(i + 1) % polygon.len()]));
+    let distance = edges
+        .clone()
+        .map(|(a, b)| segment_distance(point, a, b))
+        .fold(f32::INFINITY, f32::min);
+    if !distance.is_finite() {
+        return None;
+    }
+    let inside = polygon.len() >= 3 && edges.into_iter().all(|(a, b)| (b - a).perp_dot(point - a) <= 0.0);
+    Some(if inside { distance } else { -distance })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Balance {
+    pub centre_of_mass: Vec3,
+    /// Height of the lowest corner of the body, what it stands on.
+    pub floor: f32,
+    /// Corners of the support polygon on the floor, in order around it.
+    pub support: Vec<Vec3>,
+    /// How far the centre of mass is inside the support polygon, seen from
+    /// above, in skeleton units. Negative when it's outside, `None` when
+    /// there are no bones.
+    pub margin: Option<f32>,
+}
+
+/// Balance of `bones` posed as `posed` under a root at `root`, standing on
+/// their lowest corners. Everything but the margin is in the space `root`
+/// is in.
+pub fn balance(bones: &[SkeletonBone], posed: &PosedSkeleton, root: Transform) -> Balance {
+    let root_matrix = root.compute_matrix();
+    let centre_of_mass = centre_of_mass(bones, posed, root_matrix);
+    let scale = root.scale.y.abs().max(f32::EPSILON);
+    let corners = bones
+        .iter()
+        .filter_map(|bone| Some((bone, root_matrix * posed.global(bone.joint_id)?)))
+        .flat_map(|(bone, global)| {
+            skeleton::bone_corners(&bone.cube)
+                .into_iter()
+                .map(move |corner| global.transform_point3(Vec3::from(corner)))
+        })
+        .collect::<Vec<Vec3>>();
+    let floor = corners.iter().map(|corner| corner.y).fold(f32::INFINITY, f32::min);
+    let contacts = corners
+        .into_iter()
+        .filter(|corner| corner.y - floor < CONTACT * scale)
+        .map(|corner| Vec2::new(corner.x, corner.z))
+        .collect::<Vec<Vec2>>();
+    let hull = convex_hull(contacts);
+    let margin = margin(Vec2::new(centre_of_mass.x, centre_of_mass.z), &hull).map(|margin| margin / scale);
+    Balance {
+        centre_of_mass,
+        floor,
+        support: hull.into_iter().map(|corner| Vec3::new(corner.x, floor, corner.y)).collect(),
+        margin,
+    }
+}
@@ -1,104 +1,578 @@
-use yogamat_wasm::Asana;
-use rusqlite::Connection;
-use std::{collections::HashMap, fs::File, io::{Read, Write}};
-use yogamat_wasm::{skeleton::Joint, AsanaData};
-
-fn main() {
-    serialize_db();
-    let data: AsanaData = deserialize_db();
-    println!("{:#?}", data.asanas);
-}
-
-fn deserialize_db() -> AsanaData {
-    let mut db_file = File::options()
-        .read(true)
-        .open("out_db.new")
-        .expect("file couldn't be read");
-    let mut db = Vec::new();
-    let _bytes_read = db_file.read_to_end(&mut db).expect("error reading file");
-    bincode::decode_from_slice(&db, bincode::config::legacy())
-        .unwrap()
-        .0
-}
-
-#[cfg(not(target_arch = "wasm32"))]
-fn get_asanas_from_db() -> Vec<Asana> {
-    let path = "./yogamatdb.sql";
-    let db = Connection::open(path).expect("couldn't open database");
-    let sql = r#"
-SELECT a.poseId, a.asanaID, b.sanskritName, b.englishName, b.userNotes
-FROM pose a, asana b
-WHERE a.asanaID = b.asanaID;
-"#;
-    let mut stmt = db.prepare(sql).expect("trouble preparing statement");
-    let response = stmt
-        .query_map([], |row| {
-            Ok(Asana {
-                pose_id: row.get(0).expect("poseId"),
-                asana_id: row.get(1).expect("asanaID"),
-                sanskrit: row.get(2).expect("sanskritName"),
-                english: row.get(3).expect("englishName"),
-                notes: row.get(4).expect("userNotes"),
-            })
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use yogamat_wasm::{
+    collision, db, dristi, format, gltf, limits, mirror,
+    sequence::{self, Pacing, Sequence},
+    skeleton::Joint,
+    text,
+    validate::{self, JOINT_IDS},
+    Asana, AsanaData,
+};
+
+/// Tool for the YogaMat pose database.
+///
+/// Exit codes: 0 success, 1 validation problems, joints out of range, bones
+/// passing through each other or differences found, 2 the command couldn't run.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Read the SQLite database and write out_db or a text copy of it.
+    Export {
+        #[arg(short, long, default_value = db::SQLITE_PATH)]
+        input: PathBuf,
+        #[arg(short, long, default_value = db::OUT_DB_PATH)]
+        output: PathBuf,
+        /// Defaults to tree for an existing directory, otherwise the output's
+        /// extension, bincode when it has none.
+        #[arg(long, value_enum)]
+        to: Option<DataFormat>,
+        /// Dristi and diacritic names to merge in.
+        #[arg(long, default_value = db::DRISTI_CSV_PATH)]
+        dristi: PathBuf,
+    },
+    /// Write out_db or a text copy back into a SQLite database, replacing its contents.
+    Import {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Defaults to tree for a directory, otherwise the input's extension,
+        /// bincode when it has none.
+        #[arg(long, value_enum)]
+        from: Option<DataFormat>,
+        #[arg(short, long, default_value = db::SQLITE_PATH)]
+        output: PathBuf,
+    },
+    /// Check that every asana has a pose, every pose a full skeleton and
+    /// every joint unit length, orthogonal axes.
+    Validate {
+        #[arg(default_value = db::OUT_DB_PATH)]
+        input: PathBuf,
+        #[arg(long, value_enum)]
+        from: Option<DataFormat>,
+        /// Re-orthonormalize drifted axes and fill missing joints from Tadasana.
+        #[arg(long)]
+        repair: bool,
+        /// Where to write the repaired data, defaults to the input.
+        #[arg(short, long, requires = "repair")]
+        output: Option<PathBuf>,
+    },
+    /// List the joints of every pose that are turned past their anatomical
+    /// range of motion.
+    Limits {
+        #[arg(default_value = db::OUT_DB_PATH)]
+        input: PathBuf,
+        #[arg(long, value_enum)]
+        from: Option<DataFormat>,
+    },
+    /// List the bones of every pose that pass through each other.
+    Collisions {
+        #[arg(default_value = db::OUT_DB_PATH)]
+        input: PathBuf,
+        #[arg(long, value_enum)]
+        from: Option<DataFormat>,
+    },
+    /// Add the other side of asanas as new asanas, named with " (other side)".
+    Mirror {
+        #[arg(default_value = db::OUT_DB_PATH)]
+        input: PathBuf,
+        #[arg(long, value_enum)]
+        from: Option<DataFormat>,
+        /// Sanskrit name of an asana to mirror, can be repeated. Every
+        /// asymmetric asana when none is given.
+        #[arg(short, long)]
+        asana: Vec<String>,
+        /// Where to write the data with the mirrored asanas, defaults to the input.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write a sequence played on the skeleton as a binary glTF file, with
+    /// the bones skinned and the sequence as an animation.
+    Gltf {
+        #[arg(default_value = db::OUT_DB_PATH)]
+        input: PathBuf,
+        #[arg(long, value_enum)]
+        from: Option<DataFormat>,
+        /// Name of a built-in sequence or a JSON file of one.
+        #[arg(short, long, default_value = "Surya Namaskar A")]
+        sequence: String,
+        /// Pace the steps by breath instead of their hold and transition times.
+        #[arg(long, value_parser = breath_rate)]
+        breaths_per_minute: Option<f32>,
+        #[arg(short, long, default_value = "yogamat.glb")]
+        output: PathBuf,
+    },
+    /// Count asanas per category and joints per pose.
+    Stats {
+        #[arg(default_value = db::OUT_DB_PATH)]
+        input: PathBuf,
+        #[arg(long, value_enum)]
+        from: Option<DataFormat>,
+    },
+    /// Compare two databases asana by asana and joint by joint.
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        #[arg(long, value_enum)]
+        old_from: Option<DataFormat>,
+        #[arg(long, value_enum)]
+        new_from: Option<DataFormat>,
+        /// Largest change in a joint vector component that still counts as equal.
+        #[arg(long, default_value_t = 1e-4, value_parser = tolerance)]
+        tolerance: f32,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DataFormat {
+    Sqlite,
+    Bincode,
+    Json,
+    /// A directory with one JSON file per asana, see `yogamat_wasm::text`.
+    Tree,
+}
+
+impl DataFormat {
+    fn of(path: &Path, given: Option<DataFormat>) -> DataFormat {
+        given.unwrap_or_else(|| {
+            if path.is_dir() {
+                return DataFormat::Tree;
+            }
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("sql" | "sqlite" | "db") => DataFormat::Sqlite,
+                Some("json") => DataFormat::Json,
+                _ => DataFormat::Bincode,
+            }
         })
-        .expect("bad");
-    response
-        .filter_map(|result| result.ok())
-        .collect::<Vec<Asana>>()
-}
-
-#[cfg(not(target_arch = "wasm32"))]
-fn serialize_db() {
-    let asanas = get_asanas_from_db();
-    let mut data = AsanaData {
-        asanas,
-        poses: HashMap::new(),
+    }
+}
+
+/// A breath rate for `--breaths-per-minute`, a number above 0.
+fn breath_rate(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(per_minute) if per_minute.is_finite() && per_minute > 0.0 => Ok(per_minute),
+        Ok(_) => Err("must be a number of breaths above 0".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A tolerance for `--tolerance`, a number from 0 up.
+fn tolerance(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(tolerance) if tolerance.is_finite() && tolerance >= 0.0 => Ok(tolerance),
+        Ok(_) => Err("must be a number from 0 up".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Export {
+            input,
+            output,
+            to,
+            dristi,
+        } => export(&input, &output, DataFormat::of(&output, to), &dristi),
+        Command::Import {
+            input,
+            from,
+            output,
+        } => read(&input, DataFormat::of(&input, from)).and_then(|data| {
+            write(&data, &output, DataFormat::Sqlite)?;
+            println!("{} asanas written to {}", data.asanas.len(), output.display());
+            Ok(true)
+        }),
+        Command::Validate {
+            input,
+            from,
+            repair,
+            output,
+        } => check(&input, DataFormat::of(&input, from), repair, output.as_deref()),
+        Command::Limits { input, from } => {
+            read(&input, DataFormat::of(&input, from)).map(|data| check_limits(&data))
+        }
+        Command::Collisions { input, from } => {
+            read(&input, DataFormat::of(&input, from)).map(|data| check_collisions(&data))
+        }
+        Command::Mirror {
+            input,
+            from,
+            asana,
+            output,
+        } => mirror_asanas(&input, DataFormat::of(&input, from), &asana, output.as_deref()),
+        Command::Gltf {
+            input,
+            from,
+            sequence,
+            breaths_per_minute,
+            output,
+        } => read(&input, DataFormat::of(&input, from)).and_then(|data| {
+            let pacing = breaths_per_minute.map_or(Pacing::Seconds, |per_minute| Pacing::Breath { per_minute });
+            export_gltf(&data, &find_sequence(&sequence)?, pacing, &output)
+        }),
+        Command::Stats { input, from } => read(&input, DataFormat::of(&input, from)).map(|data| {
+            stats(&data);
+            true
+        }),
+        Command::Diff {
+            old,
+            new,
+            old_from,
+            new_from,
+            tolerance,
+        } => read(&old, DataFormat::of(&old, old_from)).and_then(|old_data| {
+            let new_data = read(&new, DataFormat::of(&new, new_from))?;
+            Ok(diff(&old_data, &new_data, tolerance))
+        }),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn read(path: &Path, data_format: DataFormat) -> Result<AsanaData, Box<dyn Error>> {
+    let read = || -> Result<AsanaData, Box<dyn Error>> {
+        let data = match data_format {
+            DataFormat::Sqlite => db::read_asana_data(path)?,
+            DataFormat::Bincode => format::decode(&std::fs::read(path)?)?,
+            DataFormat::Json => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+            DataFormat::Tree => text::read_tree(path)?,
+        };
+        Ok(data)
+    };
+    read().map_err(|e| format!("{}: {e}", path.display()).into())
+}
+
+fn write(data: &AsanaData, path: &Path, data_format: DataFormat) -> Result<(), Box<dyn Error>> {
+    let write = || -> Result<(), Box<dyn Error>> {
+        match data_format {
+            DataFormat::Sqlite => db::write_asana_data(path, data)?,
+            DataFormat::Bincode => db::write_encoded(data, path)?,
+            DataFormat::Json => std::fs::write(path, serde_json::to_string_pretty(data)?)?,
+            DataFormat::Tree => text::write_tree(data, path)?,
+        }
+        Ok(())
+    };
+    write().map_err(|e| format!("{}: {e}", path.display()).into())
+}
+
+fn export(
+    input: &Path,
+    output: &Path,
+    data_format: DataFormat,
+    dristi_csv: &Path,
+) -> Result<bool, Box<dyn Error>> {
+    let mut data = read(input, DataFormat::Sqlite)?;
+    let csv = std::fs::read_to_string(dristi_csv)
+        .map_err(|e| format!("couldn't read {}: {e}", dristi_csv.display()))?;
+    let merged = dristi::merge(&mut data, &dristi::parse_csv(&csv));
+    println!("dristi found for {merged} of {} asanas", data.asanas.len());
+    write(&data, output, data_format)?;
+    println!("{} asanas written to {}", data.asanas.len(), output.display());
+    Ok(true)
+}
+
+fn print_report(report: &validate::Report) -> bool {
+    for issue in report.issues.iter() {
+        println!("{issue}");
+    }
+    println!(
+        "{} asanas checked, {} problems",
+        report.asanas_checked,
+        report.issues.len()
+    );
+    report.is_clean()
+}
+
+/// With `repair` the repaired data is written to `output`, or back over
+/// `input` when no output is given.
+fn check(
+    input: &Path,
+    data_format: DataFormat,
+    repair: bool,
+    output: Option<&Path>,
+) -> Result<bool, Box<dyn Error>> {
+    let mut data = read(input, data_format)?;
+    if !repair {
+        return Ok(print_report(&validate::validate(&data)));
+    }
+    let repaired = validate::repair(&mut data);
+    for issue in repaired.iter() {
+        println!("repaired {issue}");
+    }
+    let (output, output_format) = match output {
+        Some(output) => (output, DataFormat::of(output, None)),
+        None => (input, data_format),
     };
+    write(&data, output, output_format)?;
+    println!("{} repairs written to {}", repaired.len(), output.display());
+    Ok(print_report(&validate::validate(&data)))
+}
 
-    let path = "./yogamatdb.sql";
-    let db = Connection::open(path).expect("couldn't open database");
-
-    for asana in data.asanas.iter() {
-        let sql = format!("select * from joint where poseID = {};", asana.pose_id);
-        let mut stmt = db.prepare(&sql).expect("trouble preparing statement");
-        let response = stmt
-            .query_map([], |row| {
-                Ok(Joint {
-                    joint_id: row.get(0).expect("so may results"),
-                    pose_id: row.get(1).expect("so may results"),
-                    up_x: row.get(2).expect("so may results"),
-                    up_y: row.get(3).expect("so may results"),
-                    up_z: row.get(4).expect("so may results"),
-                    forward_x: row.get(5).expect("so may results"),
-                    forward_y: row.get(6).expect("so may results"),
-                    forward_z: row.get(7).expect("so may results"),
-                    origin_x: row.get(8).expect("so may results"),
-                    origin_y: row.get(9).expect("so may results"),
-                    origin_z: row.get(10).expect("so may results"),
-                })
+fn check_limits(data: &AsanaData) -> bool {
+    let report = limits::check(data);
+    for asana in report.iter() {
+        for violation in asana.violations.iter() {
+            println!("{} ({}): {violation}", asana.sanskrit.trim(), asana.asana_id);
+        }
+    }
+    println!(
+        "{} asanas checked, {} with joints out of range",
+        data.asanas.len(),
+        report.len()
+    );
+    report.is_empty()
+}
+
+fn check_collisions(data: &AsanaData) -> bool {
+    let report = collision::check(data);
+    for asana in report.iter() {
+        for collision in asana.collisions.iter() {
+            println!("{} ({}): {collision}", asana.sanskrit.trim(), asana.asana_id);
+        }
+    }
+    println!(
+        "{} asanas checked, {} with bones passing through each other",
+        data.asanas.len(),
+        report.len()
+    );
+    report.is_empty()
+}
+
+/// Mirrors the asanas named `names`, or every asymmetric one, and writes
+/// the result to `output` or back over `input`.
+fn mirror_asanas(
+    input: &Path,
+    data_format: DataFormat,
+    names: &[String],
+    output: Option<&Path>,
+) -> Result<bool, Box<dyn Error>> {
+    let mut data = read(input, data_format)?;
+    let asana_ids = if names.is_empty() {
+        mirror::asymmetric(&data)
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                data.asanas
+                    .iter()
+                    .find(|asana| asana.sanskrit.trim() == name.trim())
+                    .map(|asana| asana.asana_id)
+                    .ok_or_else(|| format!("no asana is named {name}"))
             })
-            .expect("bad");
-        let joints = response
-            .filter_map(|result| result.ok())
-            .collect::<Vec<Joint>>();
-        let already = data.poses.insert(asana.pose_id, joints);
-        assert!(already.is_none());
-    }
-    let config = bincode::config::standard()
-        .with_little_endian()
-        .with_fixed_int_encoding();
-
-    let encoded: Vec<u8> = bincode::encode_to_vec(&data, config).unwrap();
-    let mut out_file = File::options()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open("out_db.new")
-        .expect("file couldn't be created");
-    let success = out_file.write_all(&encoded);
-    match success {
-        Ok(_) => println!("encoded db written to out_db"),
-        Err(_) => panic!("encoded db file write failed"),
+            .collect::<Result<Vec<i32>, String>>()?
+    };
+    let added = mirror::add_mirrored(&mut data, &asana_ids);
+    for (_original, mirrored) in added.iter() {
+        if let Some(asana) = data.asanas.iter().find(|asana| asana.asana_id == *mirrored) {
+            println!("added {} ({})", asana.sanskrit, asana.asana_id);
+        }
+    }
+    let (output, output_format) = match output {
+        Some(output) => (output, DataFormat::of(output, None)),
+        None => (input, data_format),
+    };
+    write(&data, output, output_format)?;
+    println!(
+        "{} of {} asanas mirrored, written to {}",
+        added.len(),
+        asana_ids.len(),
+        output.display()
+    );
+    Ok(true)
+}
+
+/// The built-in sequence called `name`, or the one in the JSON file at it.
+fn find_sequence(name: &str) -> Result<Sequence, Box<dyn Error>> {
+    if let Some(sequence) = sequence::built_in().into_iter().find(|sequence| sequence.name == name) {
+        return Ok(sequence);
     }
+    let path = Path::new(name);
+    if !path.is_file() {
+        return Err(format!("no sequence is named {name}").into());
+    }
+    let json = std::fs::read_to_string(path).map_err(|e| format!("{name}: {e}"))?;
+    Ok(serde_json::from_str(&json).map_err(|e| format!("{name}: {e}"))?)
 }
 
+fn export_gltf(data: &AsanaData, sequence: &Sequence, pacing: Pacing, output: &Path) -> Result<bool, Box<dyn Error>> {
+    let glb = gltf::sequence_glb(data, sequence, pacing)?;
+    std::fs::write(output, glb).map_err(|e| format!("{}: {e}", output.display()))?;
+    println!(
+        "{}, {} steps, written to {}",
+        sequence.name,
+        sequence.steps.len(),
+        output.display()
+    );
+    Ok(true)
+}
+
+fn stats(data: &AsanaData) {
+    let joint_count = data.poses.values().map(Vec::len).sum::<usize>();
+    println!(
+        "{} asanas, {} poses, {} joints, {} with dristi",
+        data.asanas.len(),
+        data.poses.len(),
+        joint_count,
+        data.asanas.iter().filter(|asana| asana.dristi.is_some()).count()
+    );
+
+    println!("\nasanas per category");
+    for category in data.categories.iter() {
+        let count = data
+            .asanas
+            .iter()
+            .filter(|asana| asana.category_ids.contains(&category.category_id))
+            .count();
+        println!("{count:>5}  {}", category.name);
+    }
+    let uncategorized = data
+        .asanas
+        .iter()
+        .filter(|asana| asana.category_ids.is_empty())
+        .count();
+    println!("{uncategorized:>5}  (none)");
+
+    println!("\nposes with each joint, of {}", data.poses.len());
+    let mut coverage: BTreeMap<i32, usize> = JOINT_IDS.map(|joint_id| (joint_id, 0)).collect();
+    for joints in data.poses.values() {
+        for joint in joints {
+            *coverage.entry(joint.joint_id).or_default() += 1;
+        }
+    }
+    for row in coverage.iter().collect::<Vec<_>>().chunks(8) {
+        let line = row
+            .iter()
+            .map(|(joint_id, count)| format!("{joint_id:>3}:{count:<4}"))
+            .collect::<Vec<String>>()
+            .join(" ");
+        println!("{line}");
+    }
+}
+
+fn diff(old: &AsanaData, new: &AsanaData, tolerance: f32) -> bool {
+    let old_asanas = by_id(&old.asanas);
+    let new_asanas = by_id(&new.asanas);
+    let mut differences = 0;
+
+    for (asana_id, asana) in old_asanas.iter() {
+        if !new_asanas.contains_key(asana_id) {
+            println!("- {asana_id} {}", asana.sanskrit);
+            differences += 1;
+        }
+    }
+    for (asana_id, asana) in new_asanas.iter() {
+        if !old_asanas.contains_key(asana_id) {
+            println!("+ {asana_id} {}", asana.sanskrit);
+            differences += 1;
+        }
+    }
+    for (asana_id, old_asana) in old_asanas.iter() {
+        let Some(new_asana) = new_asanas.get(asana_id) else {
+            continue;
+        };
+        let mut changes = Vec::new();
+        if old_asana.sanskrit != new_asana.sanskrit {
+            changes.push(format!("sanskrit {:?} -> {:?}", old_asana.sanskrit, new_asana.sanskrit));
+        }
+        if old_asana.english != new_asana.english {
+            changes.push(format!("english {:?} -> {:?}", old_asana.english, new_asana.english));
+        }
+        if old_asana.unicode != new_asana.unicode {
+            changes.push(format!("unicode {:?} -> {:?}", old_asana.unicode, new_asana.unicode));
+        }
+        if old_asana.notes != new_asana.notes {
+            changes.push("notes changed".to_string());
+        }
+        if old_asana.category_ids != new_asana.category_ids {
+            changes.push(format!(
+                "categories {:?} -> {:?}",
+                old_asana.category_ids, new_asana.category_ids
+            ));
+        }
+        if old_asana.dristi != new_asana.dristi {
+            changes.push(format!("dristi {:?} -> {:?}", old_asana.dristi, new_asana.dristi));
+        }
+        if old_asana.pose_id != new_asana.pose_id {
+            changes.push(format!("pose {} -> {}", old_asana.pose_id, new_asana.pose_id));
+        }
+        let no_joints = Vec::new();
+        let old_joints = old.poses.get(&old_asana.pose_id).unwrap_or(&no_joints);
+        let new_joints = new.poses.get(&new_asana.pose_id).unwrap_or(&no_joints);
+        changes.extend(diff_joints(old_joints, new_joints, tolerance));
+
+        if !changes.is_empty() {
+            println!("~ {asana_id} {}", old_asana.sanskrit);
+            for change in changes.iter() {
+                println!("    {change}");
+            }
+            differences += 1;
+        }
+    }
+    println!("{differences} asanas differ");
+    differences == 0
+}
+
+fn by_id(asanas: &[Asana]) -> BTreeMap<i32, &Asana> {
+    asanas.iter().map(|asana| (asana.asana_id, asana)).collect()
+}
+
+fn diff_joints(old: &[Joint], new: &[Joint], tolerance: f32) -> Vec<String> {
+    let old = old
+        .iter()
+        .map(|joint| (joint.joint_id, joint))
+        .collect::<BTreeMap<i32, &Joint>>();
+    let new = new
+        .iter()
+        .map(|joint| (joint.joint_id, joint))
+        .collect::<BTreeMap<i32, &Joint>>();
+    let mut changes = Vec::new();
+    for joint_id in old.keys().chain(new.keys()).collect::<BTreeSet<&i32>>() {
+        match (old.get(joint_id), new.get(joint_id)) {
+            (Some(_), None) => changes.push(format!("joint {joint_id} removed")),
+            (None, Some(_)) => changes.push(format!("joint {joint_id} added")),
+            (Some(old_joint), Some(new_joint)) => {
+                for (name, before, after) in vectors(old_joint)
+                    .into_iter()
+                    .zip(vectors(new_joint))
+                    .map(|((name, before), (_name, after))| (name, before, after))
+                {
+                    let delta = before
+                        .iter()
+                        .zip(after.iter())
+                        .map(|(b, a)| (a - b).abs())
+                        .fold(0.0, f32::max);
+                    if delta > tolerance {
+                        changes.push(format!("joint {joint_id} {name} {before:?} -> {after:?}"));
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    changes
+}
+
+fn vectors(joint: &Joint) -> [(&'static str, [f32; 3]); 3] {
+    [
+        ("up", [joint.up_x, joint.up_y, joint.up_z]),
+        ("forward", [joint.forward_x, joint.forward_y, joint.forward_z]),
+        ("origin", [joint.origin_x, joint.origin_y, joint.origin_z]),
+    ]
+}
@@ -0,0 +1,219 @@
+//! A smooth body around the skeleton, skinned to its bones, as an
+//! alternative to the bone cubes of `make_bone_mesh`.
+//!
+//! Every bone gets an elliptical tube with rounded ends, sized from its
+//! `BoneCube`. Near a joint the vertices are weighted half to the bone on
+//! the other side, so neighbouring tubes bend together instead of apart.
+//! The mesh is built in the bind pose and the inverse bind matrices undo it.
+
+use std::{collections::HashMap, f32::consts::PI};
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+        render_resource::PrimitiveTopology,
+    },
+};
+
+use crate::{
+    kinematics::{self, MissingJoint},
+    skeleton::{Segment, SkeletonBone},
+};
+
+/// Points around each ring.
+const SEGMENTS: usize = 16;
+/// Rings along the length of a bone.
+const RINGS: usize = 6;
+/// Rings in each rounded end.
+const CAP_RINGS: usize = 3;
+
+pub struct BodyMesh {
+    pub mesh: Mesh,
+    /// One per entry of `joint_ids`.
+    pub inverse_bindposes: Vec<Mat4>,
+    /// Joint ids in the order `SkinnedMesh::joints` has to list the bones.
+    pub joint_ids: Vec<i32>,
+}
+
+/// Half width (x) and half depth (z) of the torso from the hips (0) to the
+/// top of the thoracic spine (1), for the default vertebra width.
+const TORSO_PROFILE: [(f32, Vec2); 4] = [
+    (0.0, Vec2::new(6.5, 4.5)),
+    (0.3, Vec2::new(5.8, 4.2)),
+    (0.75, Vec2::new(7.5, 5.2)),
+    (1.0, Vec2::new(6.5, 4.5)),
+];
+const NECK_RADIUS: f32 = 2.6;
+/// `BoneCube` width of a vertebra on the default body.
+const VERTEBRA_WIDTH: f32 = 3.0;
+
+fn torso_profile(f: f32) -> Vec2 {
+    let upper = TORSO_PROFILE
+        .iter()
+        .position(|(at, _radii)| *at >= f)
+        .unwrap_or(TORSO_PROFILE.len() - 1)
+        .max(1);
+    let (from, from_radii) = TORSO_PROFILE[upper - 1];
+    let (to, to_radii) = TORSO_PROFILE[upper];
+    from_radii.lerp(to_radii, ((f - from) / (to - from)).clamp(0.0, 1.0))
+}
+
+/// Half width and half depth of the flesh at the joint end and the far end.
+fn flesh(bone: &SkeletonBone, torso: &[i32]) -> (Vec2, Vec2) {
+    let top = bone.cube.top_size() / 2.0;
+    let bottom = bone.cube.bottom_size() / 2.0;
+    let vertebra_scale = bone.cube.top_size().x / VERTEBRA_WIDTH;
+    match bone.segment {
+        Segment::Pelvis => (
+            Vec2::new(top.x, top.y.max(top.x * 0.6)),
+            Vec2::new(bottom.x, bottom.y.max(bottom.x * 0.6)),
+        ),
+        Segment::Torso => {
+            let index = torso.iter().position(|id| *id == bone.joint_id).unwrap_or(0) as f32;
+            let count = torso.len().max(1) as f32;
+            (
+                torso_profile(index / count) * vertebra_scale,
+                torso_profile((index + 1.0) / count) * vertebra_scale,
+            )
+        }
+        Segment::Neck => (
+            Vec2::splat(NECK_RADIUS * vertebra_scale),
+            Vec2::splat(NECK_RADIUS * vertebra_scale),
+        ),
+        Segment::Head => (top * 0.8, bottom * 0.8),
+        Segment::Shoulder => (top * 1.3, bottom * 1.3),
+        _ => (top, bottom),
+    }
+}
+
+/// Bone the far end of `bone` blends into: its only child, or the child
+/// carrying on the same part of the body, as C6 does from C7.
+fn continuation(bone: &SkeletonBone, bones: &[SkeletonBone]) -> Option<i32> {
+    let children = bones
+        .iter()
+        .filter(|child| child.parent == Some(bone.joint_id))
+        .collect::<Vec<&SkeletonBone>>();
+    if let [only] = children.as_slice() {
+        return Some(only.joint_id);
+    }
+    children
+        .iter()
+        .find(|child| child.segment == bone.segment)
+        .map(|child| child.joint_id)
+}
+
+/// Builds the body for `bones` bound in `pose`, parent-relative joint
+/// matrices such as `pose::pose_matrices` returns for Tadasana. Fails when
+/// `pose` misses one of the bones.
+pub fn make_body_mesh(bones: &[SkeletonBone], pose: &[(i32, Mat4)]) -> Result<BodyMesh, MissingJoint> {
+    let bind_pose = kinematics::pose_skeleton(bones, Mat4::IDENTITY, pose)?;
+    let globals = bind_pose
+        .bones
+        .iter()
+        .map(|bone| (bone.joint_id, bone.global))
+        .collect::<HashMap<i32, Mat4>>();
+    let joint_ids = bones.iter().map(|bone| bone.joint_id).collect::<Vec<i32>>();
+    let index_of = |joint_id: i32| joint_ids.iter().position(|id| *id == joint_id).unwrap_or(0) as u16;
+    let torso = bones
+        .iter()
+        .filter(|bone| bone.segment == Segment::Torso)
+        .map(|bone| bone.joint_id)
+        .collect::<Vec<i32>>();
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut joints: Vec<[u16; 4]> = Vec::new();
+    let mut weights: Vec<[f32; 4]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for bone in bones {
+        let global = globals[&bone.joint_id];
+        let (top_radii, bottom_radii) = flesh(bone, &torso);
+        let length = bone.cube.y;
+        let top_y = bone.cube.transform.translation.y + length / 2.0;
+        let bottom_y = top_y - length;
+        let blend = if matches!(bone.segment, Segment::Torso | Segment::Neck) {
+            0.5
+        } else {
+            0.25
+        };
+        let parent = bone.parent.map(index_of);
+        let child = continuation(bone, bones).map(index_of);
+        let own = index_of(bone.joint_id);
+        let skin = |t: f32| {
+            let to_parent = parent.map_or(0.0, |_| 0.5 * (1.0 - t / blend).max(0.0));
+            let to_child = child.map_or(0.0, |_| 0.5 * ((t - (1.0 - blend)) / blend).max(0.0));
+            (
+                [own, parent.unwrap_or(0), child.unwrap_or(0), 0],
+                [1.0 - to_parent - to_child, to_parent, to_child, 0.0],
+            )
+        };
+
+        // (y, radii, t) from the top of the rounded joint end down
+        let top_cap = top_radii.min_element() * 0.6;
+        let bottom_cap = bottom_radii.min_element() * 0.6;
+        let mut rings = Vec::new();
+        for i in (1..=CAP_RINGS).rev() {
+            let angle = i as f32 / (CAP_RINGS + 1) as f32 * PI / 2.0;
+            rings.push((top_y + top_cap * angle.sin(), top_radii * angle.cos(), 0.0));
+        }
+        for i in 0..RINGS {
+            let t = i as f32 / (RINGS - 1) as f32;
+            rings.push((top_y - length * t, top_radii.lerp(bottom_radii, t), t));
+        }
+        for i in 1..=CAP_RINGS {
+            let angle = i as f32 / (CAP_RINGS + 1) as f32 * PI / 2.0;
+            rings.push((bottom_y - bottom_cap * angle.sin(), bottom_radii * angle.cos(), 1.0));
+        }
+
+        let mut push = |local: Vec3, t: f32| {
+            positions.push(global.transform_point3(local).into());
+            let (joint, weight) = skin(t);
+            joints.push(joint);
+            weights.push(weight);
+            positions.len() as u32 - 1
+        };
+        let top_pole = push(Vec3::new(0.0, top_y + top_cap, 0.0), 0.0);
+        let first_ring = top_pole + 1;
+        for (y, radii, t) in rings.iter() {
+            for k in 0..SEGMENTS {
+                let angle = k as f32 / SEGMENTS as f32 * 2.0 * PI;
+                push(Vec3::new(radii.x * angle.cos(), *y, radii.y * angle.sin()), *t);
+            }
+        }
+        let bottom_pole = push(Vec3::new(0.0, bottom_y - bottom_cap, 0.0), 1.0);
+
+        let vertex = |ring: usize, k: usize| first_ring + (ring * SEGMENTS + k % SEGMENTS) as u32;
+        for k in 0..SEGMENTS {
+            indices.extend([top_pole, vertex(0, k + 1), vertex(0, k)]);
+        }
+        for ring in 0..rings.len() - 1 {
+            for k in 0..SEGMENTS {
+                let (a, b) = (vertex(ring, k), vertex(ring, k + 1));
+                let (c, d) = (vertex(ring + 1, k), vertex(ring + 1, k + 1));
+                indices.extend([a, b, c, b, d, c]);
+            }
+        }
+        let last = rings.len() - 1;
+        for k in 0..SEGMENTS {
+            indices.extend([bottom_pole, vertex(last, k), vertex(last, k + 1)]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(joints),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, weights)
+        .with_inserted_indices(Indices::U32(indices));
+    mesh.compute_smooth_normals();
+
+    Ok(BodyMesh {
+        mesh,
+        inverse_bindposes: joint_ids.iter().map(|joint_id| globals[joint_id].inverse()).collect(),
+        joint_ids,
+    })
+}
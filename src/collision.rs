@@ -0,0 +1,180 @@
+//! Bones that pass through each other.
+//!
+//! Each bone is boxed by the smallest box around its mesh in the bone's own
+//! space, which turns and moves with the bone, and two boxes overlap when no
+//! separating axis between them can be found. The blocks are coarser than a
+//! body, so bones resting against each other overlap too; a bone only passes
+//! through another once the boxes overlap by more than the thinner of the two
+//! is thick. Bones that meet at a joint always overlap there, so a bone isn't
+//! tested against its parent, its children or the other children of its
+//! parent. Hands aren't tested at all: a hand holds what it touches, a foot,
+//! the other hand or the back of the head, and its block can't close round
+//! it, so it sinks right into the block of whatever it holds.
+
+use std::fmt;
+
+use bevy::math::{Mat4, Vec3};
+
+use crate::{
+    kinematics::{self, MissingJoint, PosedSkeleton},
+    skeleton::{self, BoneCube, Joint, Segment, SkeletonBone},
+    AsanaData,
+};
+
+/// A box turned and moved with a bone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoneBox {
+    pub centre: Vec3,
+    /// Unit x, y and z axes of the bone.
+    pub axes: [Vec3; 3],
+    /// Half the size of the box along each axis.
+    pub half_size: Vec3,
+}
+
+impl BoneBox {
+    /// The box around the mesh `make_bone_mesh` builds for `cube`, placed by
+    /// the bone's `global` matrix.
+    pub fn new(cube: &BoneCube, global: Mat4) -> BoneBox {
+        let (min, max) = skeleton::bone_corners(cube)
+            .into_iter()
+            .map(Vec3::from)
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), corner| (min.min(corner), max.max(corner)));
+        let axes = [global.x_axis, global.y_axis, global.z_axis].map(|axis| axis.truncate());
+        BoneBox {
+            centre: global.transform_point3((min + max) / 2.0),
+            axes: axes.map(Vec3::normalize_or_zero),
+            half_size: (max - min) / 2.0 * Vec3::from(axes.map(Vec3::length)),
+        }
+    }
+
+    /// Size of the box across its thinnest side.
+    pub fn thickness(&self) -> f32 {
+        self.half_size.min_element() * 2.0
+    }
+
+    /// Half the length of the box's shadow on `axis`.
+    fn radius(&self, axis: Vec3) -> f32 {
+        (0..3)
+            .map(|i| self.half_size[i] * self.axes[i].dot(axis).abs())
+            .sum()
+    }
+
+    /// How far the boxes would have to move apart to stop overlapping, `None`
+    /// when they don't.
+    pub fn overlap(&self, other: &BoneBox) -> Option<f32> {
+        let crossed = self
+            .axes
+            .iter()
+            .flat_map(|a| other.axes.iter().map(move |b| a.cross(*b)));
+        let between = other.centre - self.centre;
+        self.axes
+            .into_iter()
+            .chain(other.axes)
+            .chain(crossed)
+            // Parallel edges cross to nothing, a face axis separates them.
+            .filter_map(|axis| axis.try_normalize())
+            .map(|axis| self.radius(axis) + other.radius(axis) - between.dot(axis).abs())
+            .try_fold(f32::MAX, |depth, overlap| (overlap > 0.0).then_some(depth.min(overlap)))
+    }
+}
+
+/// Two bones that pass through each other, the lower joint id first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+    pub joints: (i32, i32),
+    /// How far the boxes of the bones overlap.
+    pub depth: f32,
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |joint_id: i32| {
+            skeleton::find_bone(joint_id)
+                .map(|bone| bone.cube.name.clone())
+                .unwrap_or_else(|| format!("joint {joint_id}"))
+        };
+        write!(
+            f,
+            "{} passes through {} by {:.1}",
+            name(self.joints.0),
+            name(self.joints.1),
+            self.depth
+        )
+    }
+}
+
+/// Whether bones `a` and `b` meet at a joint.
+pub fn adjacent(a: &SkeletonBone, b: &SkeletonBone) -> bool {
+    a.parent == Some(b.joint_id) || b.parent == Some(a.joint_id) || (a.parent.is_some() && a.parent == b.parent)
+}
+
+/// Whether bones `a` and `b` overlap however they're posed: the neck runs
+/// up into the head, which hangs from its bottom vertebra.
+fn inside(a: &SkeletonBone, b: &SkeletonBone) -> bool {
+    matches!((a.segment, b.segment), (Segment::Head, Segment::Neck) | (Segment::Neck, Segment::Head))
+}
+
+/// Whether `bone` grips what it touches, see the module docs.
+fn holds(bone: &SkeletonBone) -> bool {
+    bone.segment == Segment::Hand
+}
+
+/// Every pair of `bones`, posed as `posed`, that pass through each other,
+/// deepest first.
+pub fn collisions(bones: &[SkeletonBone], posed: &PosedSkeleton) -> Vec<Collision> {
+    let boxes = bones
+        .iter()
+        .filter(|bone| !holds(bone))
+        .filter_map(|bone| Some((bone, BoneBox::new(&bone.cube, posed.global(bone.joint_id)?))))
+        .collect::<Vec<(&SkeletonBone, BoneBox)>>();
+    let mut found = Vec::new();
+    for (i, (a, a_box)) in boxes.iter().enumerate() {
+        for (b, b_box) in boxes[i + 1..].iter() {
+            if adjacent(a, b) || inside(a, b) {
+                continue;
+            }
+            let thinner = a_box.thickness().min(b_box.thickness());
+            let Some(depth) = a_box.overlap(b_box).filter(|depth| *depth > thinner) else {
+                continue;
+            };
+            found.push(Collision {
+                joints: (a.joint_id.min(b.joint_id), a.joint_id.max(b.joint_id)),
+                depth,
+            });
+        }
+    }
+    found.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+    found
+}
+
+/// `collisions` of the default skeleton posed by the joints of a pose as
+/// they're stored.
+pub fn pose_collisions(joints: &[Joint]) -> Result<Vec<Collision>, MissingJoint> {
+    let bones = skeleton::bones();
+    let posed = kinematics::forward_kinematics(bones, Mat4::IDENTITY, joints)?;
+    Ok(collisions(bones, &posed))
+}
+
+/// The bones of one asana that pass through each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsanaCollisions {
+    pub asana_id: i32,
+    pub sanskrit: String,
+    pub collisions: Vec<Collision>,
+}
+
+/// Every asana whose pose has bones passing through each other, in the
+/// order of `data.asanas`. Poses missing a joint are left to `validate`.
+pub fn check(data: &AsanaData) -> Vec<AsanaCollisions> {
+    data.asanas
+        .iter()
+        .filter_map(|asana| {
+            let collisions = pose_collisions(data.poses.get(&asana.pose_id)?).ok()?;
+            (!collisions.is_empty()).then(|| AsanaCollisions {
+                asana_id: asana.asana_id,
+                sanskrit: asana.sanskrit.clone(),
+                collisions,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,358 @@
+use std::{collections::HashMap, fmt, fs::File, io::Write, path::Path};
+
+use bevy::math::Vec3;
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::{dristi::Dristi, format, skeleton::Joint, Asana, AsanaData, Category};
+
+pub const SQLITE_PATH: &str = "./yogamatdb.sql";
+pub const OUT_DB_PATH: &str = "./out_db";
+pub const DRISTI_CSV_PATH: &str = "./asanas_dristi.csv";
+
+#[derive(Debug)]
+pub enum DbError {
+    Sqlite(rusqlite::Error),
+    /// More than one asana row joins to the pose with this id.
+    DuplicatePose(i32),
+    /// `update_pose` found no row for this joint of the pose, one added
+    /// since the database was exported.
+    JointNotFound { pose_id: i32, joint_id: i32 },
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Sqlite(e) => write!(f, "{e}"),
+            DbError::DuplicatePose(pose_id) => write!(f, "pose {pose_id} belongs to more than one asana"),
+            DbError::JointNotFound { pose_id, joint_id } => {
+                write!(f, "pose {pose_id} has no joint {joint_id} in the database")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(e: rusqlite::Error) -> DbError {
+        DbError::Sqlite(e)
+    }
+}
+
+/// Columns `write_asana_data` adds to the legacy `asana` table, with their types.
+const ASANA_EXTRA_COLUMNS: [(&str, &str); 2] = [("unicodeName", "TEXT"), ("dristi", "INTEGER")];
+
+fn has_column(db: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = db.prepare(&format!("PRAGMA table_info({table})"))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Every asana with its pose. Databases written before `write_asana_data`
+/// stored the diacritic name and dristi have neither.
+pub fn get_asanas(db: &Connection) -> rusqlite::Result<Vec<Asana>> {
+    let extra = if has_column(db, "asana", "dristi")? {
+        "b.unicodeName, b.dristi"
+    } else {
+        "NULL, NULL"
+    };
+    let sql = format!(
+        r#"
+SELECT a.poseId, a.asanaID, b.sanskritName, b.englishName, b.userNotes, {extra}
+FROM pose a, asana b
+WHERE a.asanaID = b.asanaID;
+"#
+    );
+    let mut stmt = db.prepare(&sql)?;
+    let response = stmt.query_map([], |row| {
+        Ok(Asana {
+            pose_id: row.get(0)?,
+            asana_id: row.get(1)?,
+            sanskrit: row.get(2)?,
+            english: row.get(3)?,
+            unicode: row.get(5)?,
+            notes: row.get(4)?,
+            category_ids: Vec::new(),
+            dristi: row.get::<_, Option<u8>>(6)?.and_then(Dristi::from_code),
+        })
+    })?;
+    let mut asanas = response.collect::<rusqlite::Result<Vec<Asana>>>()?;
+    for asana in asanas.iter_mut() {
+        asana.category_ids = get_asana_category_ids(db, asana.asana_id)?;
+    }
+    Ok(asanas)
+}
+
+pub fn get_categories(db: &Connection) -> rusqlite::Result<Vec<Category>> {
+    let sql = r#"
+SELECT categoryID, name, description
+FROM asanaCategory
+ORDER BY categoryID;
+"#;
+    let mut stmt = db.prepare(sql)?;
+    let response = stmt.query_map([], |row| {
+        Ok(Category {
+            category_id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+        })
+    })?;
+    response.collect()
+}
+
+pub fn get_asana_category_ids(db: &Connection, asana_id: i32) -> rusqlite::Result<Vec<i32>> {
+    let sql = r#"
+SELECT categoryID
+FROM categoryList
+WHERE asanaID = ?
+ORDER BY categoryID;
+"#;
+    let mut stmt = db.prepare(sql)?;
+    let response = stmt.query_map([asana_id], |row| row.get(0))?;
+    response.collect()
+}
+
+pub fn get_joints(db: &Connection, pose_id: i32) -> rusqlite::Result<Vec<Joint>> {
+    let sql = r#"
+SELECT jointID, poseID, upX, upY, upZ, forwardX, forwardY, forwardZ, originX, originY, originZ,
+       xAngle, yAngle, zAngle
+FROM joint
+WHERE poseID = ?
+ORDER BY jointID;
+"#;
+    let mut stmt = db.prepare(sql)?;
+    let response = stmt.query_map([pose_id], |row| {
+        let mut joint = Joint {
+            joint_id: row.get(0)?,
+            pose_id: row.get(1)?,
+            up_x: row.get(2)?,
+            up_y: row.get(3)?,
+            up_z: row.get(4)?,
+            forward_x: row.get(5)?,
+            forward_y: row.get(6)?,
+            forward_z: row.get(7)?,
+            origin_x: row.get(8)?,
+            origin_y: row.get(9)?,
+            origin_z: row.get(10)?,
+            angles: None,
+        };
+        let legacy: (Option<f32>, Option<f32>, Option<f32>) = (row.get(11)?, row.get(12)?, row.get(13)?);
+        if let (Some(x), Some(y), Some(z)) = legacy {
+            let angles = from_legacy_angles(Vec3::new(x, y, z));
+            if joint.angles_match(angles) {
+                joint.angles = Some(angles.into());
+            }
+        }
+        Ok(joint)
+    })?;
+    response.collect()
+}
+
+/// The legacy editor's y angle is 180 at rest.
+const LEGACY_Y_OFFSET: f32 = 180.0;
+
+fn wrap_degrees(angle: f32) -> f32 {
+    let wrapped = (angle + 180.0).rem_euclid(360.0) - 180.0;
+    if wrapped == -180.0 {
+        180.0
+    } else {
+        wrapped
+    }
+}
+
+/// `xAngle`, `yAngle`, `zAngle` as `Joint` angles. Many rows hold angles
+/// that no longer match their axes, `get_joints` keeps only those that do.
+fn from_legacy_angles(legacy: Vec3) -> Vec3 {
+    Vec3::new(legacy.x, wrap_degrees(legacy.y - LEGACY_Y_OFFSET), legacy.z)
+}
+
+fn to_legacy_angles(angles: Vec3) -> Vec3 {
+    Vec3::new(angles.x, wrap_degrees(angles.y + LEGACY_Y_OFFSET), angles.z)
+}
+
+/// Reads every asana, its categories and its pose joints out of the legacy SQLite database.
+pub fn read_asana_data(path: impl AsRef<Path>) -> Result<AsanaData, DbError> {
+    let db = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let asanas = get_asanas(&db)?;
+    let categories = get_categories(&db)?;
+    let mut poses = HashMap::new();
+    for asana in asanas.iter() {
+        let joints = get_joints(&db, asana.pose_id)?;
+        if poses.insert(asana.pose_id, joints).is_some() {
+            return Err(DbError::DuplicatePose(asana.pose_id));
+        }
+    }
+    Ok(AsanaData {
+        asanas,
+        poses,
+        categories,
+    })
+}
+
+/// Writes the joints of one pose back using the UPDATE statement from
+/// schema.txt. Nothing is written if a joint has no row to update.
+pub fn update_pose(path: impl AsRef<Path>, joints: &[Joint]) -> Result<(), DbError> {
+    let mut db = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    let tx = db.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "UPDATE joint SET upX = ?, upY = ?, upZ = ?, forwardX = ?, forwardY = ?, forwardZ = ?, originX = ?, originY = ?, originZ = ?, xAngle = ?, yAngle = ?, zAngle = ? WHERE jointID = ? AND poseID = ?",
+        )?;
+        for joint in joints {
+            let angles = to_legacy_angles(joint.euler_degrees());
+            let updated = stmt.execute(params![
+                joint.up_x,
+                joint.up_y,
+                joint.up_z,
+                joint.forward_x,
+                joint.forward_y,
+                joint.forward_z,
+                joint.origin_x,
+                joint.origin_y,
+                joint.origin_z,
+                angles.x,
+                angles.y,
+                angles.z,
+                joint.joint_id,
+                joint.pose_id,
+            ])?;
+            if updated == 0 {
+                return Err(DbError::JointNotFound {
+                    pose_id: joint.pose_id,
+                    joint_id: joint.joint_id,
+                });
+            }
+        }
+    }
+    Ok(tx.commit()?)
+}
+
+/// Tables from schema.txt, for writing a database from scratch.
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS asanaCategory (
+categoryID INTEGER PRIMARY KEY,
+name TEXT,
+description TEXT
+);
+CREATE TABLE IF NOT EXISTS categoryList (
+asanaID INTEGER,
+categoryID INTEGER,
+PRIMARY KEY (asanaID, categoryID)
+);
+CREATE TABLE IF NOT EXISTS asana (
+asanaID INTEGER PRIMARY KEY,
+sanskritName TEXT,
+englishName TEXT,
+userNotes TEXT,
+unicodeName TEXT,
+dristi INTEGER
+);
+CREATE TABLE IF NOT EXISTS pose (
+poseID INTEGER PRIMARY KEY,
+asanaID INTEGER
+);
+CREATE TABLE IF NOT EXISTS joint (
+jointID INTEGER,
+poseID INTEGER,
+upX REAL,
+upY REAL,
+upZ REAL,
+forwardX REAL,
+forwardY REAL,
+forwardZ REAL,
+originX REAL,
+originY REAL,
+originZ REAL,
+xAngle REAL,
+yAngle REAL,
+zAngle REAL,
+PRIMARY KEY (jointID, poseID)
+);
+"#;
+
+/// Replaces the contents of the SQLite database at `path` with `data`,
+/// creating the file and its tables if needed. The diacritic names and
+/// dristi go in columns the legacy schema doesn't have, which are added to
+/// an existing `asana` table.
+pub fn write_asana_data(path: impl AsRef<Path>, data: &AsanaData) -> rusqlite::Result<()> {
+    let mut db = Connection::open(path)?;
+    let tx = db.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+    for (column, column_type) in ASANA_EXTRA_COLUMNS {
+        if !has_column(&tx, "asana", column)? {
+            tx.execute_batch(&format!("ALTER TABLE asana ADD COLUMN {column} {column_type};"))?;
+        }
+    }
+    tx.execute_batch(
+        "DELETE FROM joint; DELETE FROM pose; DELETE FROM asana; DELETE FROM categoryList; DELETE FROM asanaCategory;",
+    )?;
+    {
+        let mut stmt =
+            tx.prepare("INSERT INTO asanaCategory (categoryID, name, description) VALUES (?, ?, ?)")?;
+        for category in data.categories.iter() {
+            stmt.execute(params![category.category_id, category.name, category.description])?;
+        }
+        let mut asana_stmt = tx.prepare(
+            "INSERT INTO asana (asanaID, sanskritName, englishName, userNotes, unicodeName, dristi) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        let mut pose_stmt = tx.prepare("INSERT INTO pose (poseID, asanaID) VALUES (?, ?)")?;
+        let mut category_stmt =
+            tx.prepare("INSERT INTO categoryList (asanaID, categoryID) VALUES (?, ?)")?;
+        for asana in data.asanas.iter() {
+            asana_stmt.execute(params![
+                asana.asana_id,
+                asana.sanskrit,
+                asana.english,
+                asana.notes,
+                asana.unicode,
+                asana.dristi.map(|dristi| dristi as u8),
+            ])?;
+            pose_stmt.execute(params![asana.pose_id, asana.asana_id])?;
+            for category_id in asana.category_ids.iter() {
+                category_stmt.execute(params![asana.asana_id, category_id])?;
+            }
+        }
+        let mut stmt = tx.prepare(
+            "INSERT INTO joint (jointID, poseID, upX, upY, upZ, forwardX, forwardY, forwardZ, originX, originY, originZ, xAngle, yAngle, zAngle) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for joints in data.poses.values() {
+            for joint in joints {
+                let angles = to_legacy_angles(joint.euler_degrees());
+                stmt.execute(params![
+                    joint.joint_id,
+                    joint.pose_id,
+                    joint.up_x,
+                    joint.up_y,
+                    joint.up_z,
+                    joint.forward_x,
+                    joint.forward_y,
+                    joint.forward_z,
+                    joint.origin_x,
+                    joint.origin_y,
+                    joint.origin_z,
+                    angles.x,
+                    angles.y,
+                    angles.z,
+                ])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+/// Writes `data` in the versioned out_db format.
+pub fn write_encoded(data: &AsanaData, path: impl AsRef<Path>) -> std::io::Result<()> {
+    let encoded = format::encode(data)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let mut out_file = File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    out_file.write_all(&encoded)
+}
@@ -0,0 +1,130 @@
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::AsanaData;
+
+/// Gaze point, numbered as in the `dristi` column of asanas_dristi.csv.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Decode, Encode)]
+pub enum Dristi {
+    Unspecified = 0,
+    /// Nasagrai
+    Nose = 1,
+    /// Broomadhya
+    ThirdEye = 2,
+    /// Nabi chakra
+    Navel = 3,
+    /// Hastagrai
+    Hand = 4,
+    /// Padhayoragrai
+    Toes = 5,
+    /// Parsva
+    FarLeft = 6,
+    /// Parsva
+    FarRight = 7,
+    /// Angusta ma dyai
+    Thumbs = 8,
+    /// Urdhva
+    Up = 9,
+}
+
+impl Dristi {
+    pub fn from_code(code: u8) -> Option<Dristi> {
+        match code {
+            0 => Some(Dristi::Unspecified),
+            1 => Some(Dristi::Nose),
+            2 => Some(Dristi::ThirdEye),
+            3 => Some(Dristi::Navel),
+            4 => Some(Dristi::Hand),
+            5 => Some(Dristi::Toes),
+            6 => Some(Dristi::FarLeft),
+            7 => Some(Dristi::FarRight),
+            8 => Some(Dristi::Thumbs),
+            9 => Some(Dristi::Up),
+            _ => None,
+        }
+    }
+
+    /// The gaze for the pose done on the other side.
+    pub fn mirrored(self) -> Dristi {
+        match self {
+            Dristi::FarLeft => Dristi::FarRight,
+            Dristi::FarRight => Dristi::FarLeft,
+            dristi => dristi,
+        }
+    }
+
+    /// Bones the gaze ray points at, by their names in `skeleton::SKELETON`.
+    /// Empty for the dristis that are a direction rather than a body part.
+    pub fn target_bones(&self) -> &'static [&'static str] {
+        match self {
+            Dristi::Navel => &["Hips"],
+            Dristi::Hand | Dristi::Thumbs => &["Left Hand", "Right Hand"],
+            Dristi::Toes => &["Left Foot", "Right Foot"],
+            _ => &[],
+        }
+    }
+}
+
+pub struct DristiRow {
+    pub sanskrit: String,
+    pub unicode: String,
+    pub dristi: Dristi,
+}
+
+/// Reads the `id,sanskrit,unicode,english,dristi` rows of asanas_dristi.csv.
+/// The file also has `id|sanskrit|english|` notes from matching it up by hand,
+/// those and anything else without exactly five fields are skipped.
+pub fn parse_csv(text: &str) -> Vec<DristiRow> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split(',').map(str::trim).collect::<Vec<&str>>();
+            if fields.len() != 5 {
+                return None;
+            }
+            Some(DristiRow {
+                sanskrit: fields[1].to_string(),
+                unicode: fields[2].to_string(),
+                dristi: Dristi::from_code(fields[4].parse().ok()?)?,
+            })
+        })
+        .collect()
+}
+
+fn normalize(sanskrit: &str) -> String {
+    sanskrit
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Database spellings that differ from the csv.
+const ALIASES: [(&str, &str); 4] = [
+    ("navasana", "paripurna navasana"),
+    ("bakasana", "bakasana a"),
+    ("yoanidrasana", "yoganidrasana"),
+    ("utpluthi", "tolasana utphutih"),
+];
+
+/// Fills in `unicode` and `dristi` for every asana whose sanskrit name is in
+/// `rows`, returning how many were matched. Variants such as "Vrksasana - 1"
+/// share the row of their base asana.
+pub fn merge(data: &mut AsanaData, rows: &[DristiRow]) -> usize {
+    let mut merged = 0;
+    for asana in data.asanas.iter_mut() {
+        let mut name = normalize(&asana.sanskrit);
+        if let Some((base, _variant)) = name.rsplit_once(" - ") {
+            name = base.to_string();
+        }
+        if let Some((_, alias)) = ALIASES.iter().find(|(db_name, _)| *db_name == name) {
+            name = alias.to_string();
+        }
+        if let Some(row) = rows.iter().find(|row| normalize(&row.sanskrit) == name) {
+            asana.unicode = Some(row.unicode.clone());
+            asana.dristi = Some(row.dristi);
+            merged += 1;
+        }
+    }
+    merged
+}
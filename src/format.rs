@@ -0,0 +1,320 @@
+//! The out_db file format.
+//!
+//! ```text
+//! magic    8 bytes  "YOGAMAT\0"
+//! version  u32 LE   FORMAT_VERSION when written
+//! checksum u32 LE   CRC-32 of the payload
+//! length   u64 LE   payload length in bytes
+//! payload           AsanaData, bincode standard config
+//! ```
+//!
+//! When `Asana`, `Category` or `Joint` change, bump `FORMAT_VERSION`, copy the old
+//! structs into a `vN` module next to `v0` and add a migration from it to
+//! `decode`. Files without the magic are the headerless bincode that was
+//! written before this format existed and are read as `v0`.
+
+use std::fmt;
+
+use bincode::{
+    config::Configuration,
+    error::{DecodeError, EncodeError},
+};
+
+use crate::AsanaData;
+
+pub const MAGIC: [u8; 8] = *b"YOGAMAT\0";
+pub const FORMAT_VERSION: u32 = 2;
+const HEADER_LEN: usize = 8 + 4 + 4 + 8;
+/// Upper bound on what decoding allocates, so a corrupt length can't panic.
+const DECODE_LIMIT: usize = 64 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum FormatError {
+    Truncated,
+    UnsupportedVersion(u32),
+    ChecksumMismatch { expected: u32, found: u32 },
+    Decode(DecodeError),
+    Encode(EncodeError),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Truncated => write!(f, "file is truncated"),
+            FormatError::UnsupportedVersion(version) if *version > FORMAT_VERSION => write!(
+                f,
+                "format version {version} is newer than this build understands ({FORMAT_VERSION})"
+            ),
+            FormatError::UnsupportedVersion(version) => write!(
+                f,
+                "format version {version} was never written, this build reads 1 to {FORMAT_VERSION}"
+            ),
+            FormatError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch, header says {expected:08x} but payload is {found:08x}"
+            ),
+            FormatError::Decode(e) => write!(f, "payload couldn't be decoded: {e}"),
+            FormatError::Encode(e) => write!(f, "payload couldn't be encoded: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+fn config() -> Configuration {
+    bincode::config::standard()
+}
+
+pub fn encode(data: &AsanaData) -> Result<Vec<u8>, FormatError> {
+    let payload = bincode::encode_to_vec(data, config()).map_err(FormatError::Encode)?;
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&crc32(&payload).to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<AsanaData, FormatError> {
+    if !bytes.starts_with(&MAGIC) {
+        let config = bincode::config::legacy().with_limit::<DECODE_LIMIT>();
+        return decode_payload::<v0::AsanaData>(bytes, config).map(Into::into);
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(FormatError::Truncated);
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    let expected = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+    let length = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+    let payload = usize::try_from(length)
+        .ok()
+        .and_then(|length| bytes[HEADER_LEN..].get(..length))
+        .ok_or(FormatError::Truncated)?;
+    let found = crc32(payload);
+    if found != expected {
+        return Err(FormatError::ChecksumMismatch { expected, found });
+    }
+    let config = config().with_limit::<DECODE_LIMIT>();
+    match version {
+        1 => decode_payload::<v1::AsanaData>(payload, config).map(Into::into),
+        FORMAT_VERSION => decode_payload(payload, config),
+        _ => Err(FormatError::UnsupportedVersion(version)),
+    }
+}
+
+fn decode_payload<T: bincode::Decode<()>>(
+    payload: &[u8],
+    config: impl bincode::config::Config,
+) -> Result<T, FormatError> {
+    bincode::decode_from_slice(payload, config)
+        .map(|(data, _bytes_read)| data)
+        .map_err(FormatError::Decode)
+}
+
+/// CRC-32 (IEEE), the same checksum zip and png use.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Headerless out_db from before categories and dristi were exported.
+mod v0 {
+    use std::collections::HashMap;
+
+    use bincode::Decode;
+
+    use super::v1::Joint;
+
+    #[derive(Decode)]
+    pub struct Asana {
+        pub asana_id: i32,
+        pub pose_id: i32,
+        pub sanskrit: String,
+        pub english: String,
+        pub notes: Option<String>,
+    }
+
+    #[derive(Decode)]
+    pub struct AsanaData {
+        pub asanas: Vec<Asana>,
+        pub poses: HashMap<i32, Vec<Joint>>,
+    }
+
+    impl From<AsanaData> for crate::AsanaData {
+        fn from(old: AsanaData) -> Self {
+            crate::AsanaData {
+                asanas: old
+                    .asanas
+                    .into_iter()
+                    .map(|asana| crate::Asana {
+                        asana_id: asana.asana_id,
+                        pose_id: asana.pose_id,
+                        sanskrit: asana.sanskrit,
+                        english: asana.english,
+                        unicode: None,
+                        notes: asana.notes,
+                        category_ids: Vec::new(),
+                        dristi: None,
+                    })
+                    .collect(),
+                poses: super::v1::migrate_poses(old.poses),
+                categories: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Joints before they carried Euler angles.
+mod v1 {
+    use std::collections::HashMap;
+
+    use bincode::Decode;
+
+    use crate::dristi;
+
+    #[derive(Decode)]
+    pub enum Dristi {
+        Unspecified,
+        Nose,
+        ThirdEye,
+        Navel,
+        Hand,
+        Toes,
+        FarLeft,
+        FarRight,
+        Thumbs,
+        Up,
+    }
+
+    #[derive(Decode)]
+    pub struct Asana {
+        pub asana_id: i32,
+        pub pose_id: i32,
+        pub sanskrit: String,
+        pub english: String,
+        pub unicode: Option<String>,
+        pub notes: Option<String>,
+        pub category_ids: Vec<i32>,
+        pub dristi: Option<Dristi>,
+    }
+
+    #[derive(Decode)]
+    pub struct Category {
+        pub category_id: i32,
+        pub name: String,
+        pub description: Option<String>,
+    }
+
+    #[derive(Decode)]
+    pub struct Joint {
+        pub joint_id: i32,
+        pub pose_id: i32,
+        pub up_x: f32,
+        pub up_y: f32,
+        pub up_z: f32,
+        pub forward_x: f32,
+        pub forward_y: f32,
+        pub forward_z: f32,
+        pub origin_x: f32,
+        pub origin_y: f32,
+        pub origin_z: f32,
+    }
+
+    #[derive(Decode)]
+    pub struct AsanaData {
+        pub asanas: Vec<Asana>,
+        pub poses: HashMap<i32, Vec<Joint>>,
+        pub categories: Vec<Category>,
+    }
+
+    pub fn migrate_poses(
+        poses: HashMap<i32, Vec<Joint>>,
+    ) -> HashMap<i32, Vec<crate::skeleton::Joint>> {
+        poses
+            .into_iter()
+            .map(|(pose_id, joints)| (pose_id, joints.into_iter().map(Into::into).collect()))
+            .collect()
+    }
+
+    impl From<Dristi> for dristi::Dristi {
+        fn from(old: Dristi) -> Self {
+            match old {
+                Dristi::Unspecified => dristi::Dristi::Unspecified,
+                Dristi::Nose => dristi::Dristi::Nose,
+                Dristi::ThirdEye => dristi::Dristi::ThirdEye,
+                Dristi::Navel => dristi::Dristi::Navel,
+                Dristi::Hand => dristi::Dristi::Hand,
+                Dristi::Toes => dristi::Dristi::Toes,
+                Dristi::FarLeft => dristi::Dristi::FarLeft,
+                Dristi::FarRight => dristi::Dristi::FarRight,
+                Dristi::Thumbs => dristi::Dristi::Thumbs,
+                Dristi::Up => dristi::Dristi::Up,
+            }
+        }
+    }
+
+    impl From<Asana> for crate::Asana {
+        fn from(old: Asana) -> Self {
+            crate::Asana {
+                asana_id: old.asana_id,
+                pose_id: old.pose_id,
+                sanskrit: old.sanskrit,
+                english: old.english,
+                unicode: old.unicode,
+                notes: old.notes,
+                category_ids: old.category_ids,
+                dristi: old.dristi.map(Into::into),
+            }
+        }
+    }
+
+    impl From<Category> for crate::Category {
+        fn from(old: Category) -> Self {
+            crate::Category {
+                category_id: old.category_id,
+                name: old.name,
+                description: old.description,
+            }
+        }
+    }
+
+    impl From<Joint> for crate::skeleton::Joint {
+        fn from(old: Joint) -> Self {
+            crate::skeleton::Joint {
+                joint_id: old.joint_id,
+                pose_id: old.pose_id,
+                up_x: old.up_x,
+                up_y: old.up_y,
+                up_z: old.up_z,
+                forward_x: old.forward_x,
+                forward_y: old.forward_y,
+                forward_z: old.forward_z,
+                origin_x: old.origin_x,
+                origin_y: old.origin_y,
+                origin_z: old.origin_z,
+                angles: None,
+            }
+        }
+    }
+
+    impl From<AsanaData> for crate::AsanaData {
+        fn from(old: AsanaData) -> Self {
+            crate::AsanaData {
+                asanas: old.asanas.into_iter().map(Into::into).collect(),
+                poses: migrate_poses(old.poses),
+                categories: old.categories.into_iter().map(Into::into).collect(),
+            }
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! The skeleton, its bone meshes and a sequence of asanas as binary glTF
+//! (`.glb`), for Blender and web viewers.
+//!
+//! Every bone is a node, parented as in `SKELETON`, and the bone nodes are
+//! the joints of one skin. The meshes `make_bone_mesh` builds are merged
+//! into one mesh skinned to it, each vertex following its own bone. The
+//! skin is bound in the first asana of the sequence, which is also the rest
+//! pose of the nodes. The animation has a rotation and a translation channel
+//! per joint, keyed where each step reaches its asana and where it leaves.
+//! Skeleton units are centimetres, a root node scales them to metres.
+
+use std::fmt;
+
+use bevy::{
+    color::{Color, ColorToComponents},
+    math::{Mat4, Quat, Vec3},
+    render::mesh::{Mesh, VertexAttributeValues},
+    transform::components::Transform,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    kinematics::{self, MissingJoint},
+    mirror,
+    sequence::{Pacing, Sequence},
+    skeleton::{self, Joint, SkeletonBone},
+    AsanaData,
+};
+
+/// Metres in a skeleton unit.
+const METRES: f32 = 0.01;
+
+const ARRAY_BUFFER: u32 = 34962;
+const FLOAT: u32 = 5126;
+const UNSIGNED_BYTE: u32 = 5121;
+
+#[derive(Debug)]
+pub enum GltfError {
+    /// The sequence has no steps.
+    Empty,
+    /// A step of the sequence names an asana, by id, that has no pose.
+    NoPose(i32),
+    MissingJoint { asana_id: i32, joint: MissingJoint },
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Empty => write!(f, "the sequence has no steps"),
+            GltfError::NoPose(asana_id) => write!(f, "asana {asana_id} has no pose"),
+            GltfError::MissingJoint { asana_id, joint } => write!(f, "asana {asana_id}: {joint}"),
+            GltfError::Json(e) => write!(f, "glTF JSON: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+/// The binary buffer of the file with its views and the accessors into it.
+#[derive(Default)]
+struct Buffer {
+    bytes: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffer {
+    /// Adds `values` as a view of its own, `kind` of them at a time, and the
+    /// accessor reading them. Positions and animation times need `bounds`.
+    fn floats(&mut self, values: &[f32], kind: &str, target: Option<u32>, bounds: bool) -> usize {
+        let bytes = values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+        let size = components(kind);
+        let mut accessor = json!({
+            "bufferView": self.view(&bytes, target),
+            "componentType": FLOAT,
+            "count": values.len() / size,
+            "type": kind,
+        });
+        if bounds {
+            let column = |i: usize| values.iter().skip(i).step_by(size).copied();
+            let min = (0..size).map(|i| column(i).fold(f32::MAX, f32::min)).collect::<Vec<f32>>();
+            let max = (0..size).map(|i| column(i).fold(f32::MIN, f32::max)).collect::<Vec<f32>>();
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn bytes(&mut self, values: &[u8], kind: &str, target: Option<u32>) -> usize {
+        let accessor = json!({
+            "bufferView": self.view(values, target),
+            "componentType": UNSIGNED_BYTE,
+            "count": values.len() / components(kind),
+            "type": kind,
+        });
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.bytes.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bytes.extend_from_slice(bytes);
+        // Every view starts 4 byte aligned.
+        self.bytes.resize(self.bytes.len().next_multiple_of(4), 0);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+}
+
+fn components(kind: &str) -> usize {
+    match kind {
+        "VEC3" => 3,
+        "VEC4" => 4,
+        "MAT4" => 16,
+        _ => 1,
+    }
+}
+
+/// The pose each step of `sequence` takes, with the time it's keyed at:
+/// when the step reaches it and, if it's held, when the next step leaves it.
+fn keys(data: &AsanaData, sequence: &Sequence, pacing: Pacing) -> Result<Vec<(f32, i32, Vec<Joint>)>, GltfError> {
+    let mut keys: Vec<(f32, i32, Vec<Joint>)> = Vec::new();
+    let mut time = 0.0;
+    for step in sequence.steps.iter() {
+        let joints = data
+            .asanas
+            .iter()
+            .find(|asana| asana.asana_id == step.asana_id)
+            .and_then(|asana| data.poses.get(&asana.pose_id))
+            .ok_or(GltfError::NoPose(step.asana_id))?;
+        let joints = if step.other_side {
+            mirror::mirror_pose(joints)
+        } else {
+            joints.clone()
+        };
+        let (transition, hold) = pacing.timing(step);
+        // The animation starts in the first asana.
+        if !keys.is_empty() {
+            time += transition;
+        }
+        let mut times = vec![time];
+        if hold > 0.0 {
+            time += hold;
+            times.push(time);
+        }
+        for time in times {
+            match keys.last_mut() {
+                // Times must increase, a later pose at the same time wins.
+                Some(last) if last.0 >= time => *last = (last.0, step.asana_id, joints.clone()),
+                _ => keys.push((time, step.asana_id, joints.clone())),
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Parent-relative transform of every bone in `joints`.
+fn locals(bones: &[SkeletonBone], asana_id: i32, joints: &[Joint]) -> Result<Vec<Transform>, GltfError> {
+    bones
+        .iter()
+        .map(|bone| {
+            joints
+                .iter()
+                .find(|joint| joint.joint_id == bone.joint_id)
+                .map(|joint| {
+                    // Nodes are posed without scale, so the bones drop any
+                    // the stored vectors drifted to.
+                    let local = Transform::from_matrix(joint.matrix());
+                    Transform::from_translation(local.translation).with_rotation(local.rotation.normalize())
+                })
+                .ok_or(GltfError::MissingJoint {
+                    asana_id,
+                    joint: MissingJoint(bone.joint_id),
+                })
+        })
+        .collect()
+}
+
+fn attribute(mesh: &Mesh, id: bevy::render::mesh::MeshVertexAttribute) -> Vec<[f32; 3]> {
+    match mesh.attribute(id) {
+        Some(VertexAttributeValues::Float32x3(values)) => values.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// `sequence` played on the default skeleton, as a `.glb` file.
+pub fn sequence_glb(data: &AsanaData, sequence: &Sequence, pacing: Pacing) -> Result<Vec<u8>, GltfError> {
+    let bones = skeleton::bones();
+    let keys = keys(data, sequence, pacing)?;
+    let Some((_time, first_id, first)) = keys.first() else {
+        return Err(GltfError::Empty);
+    };
+    let rest = locals(bones, *first_id, first)?;
+    let root = Mat4::from_scale(Vec3::splat(METRES));
+    let matrices = bones
+        .iter()
+        .zip(rest.iter())
+        .map(|(bone, local)| (bone.joint_id, local.compute_matrix()))
+        .collect::<Vec<(i32, Mat4)>>();
+    let bind = kinematics::pose_skeleton(bones, root, &matrices).map_err(|joint| GltfError::MissingJoint {
+        asana_id: *first_id,
+        joint,
+    })?;
+    // Node 0 is the root, the bones follow in the order of `bones`.
+    let node = |joint_id: i32| 1 + bones.iter().position(|bone| bone.joint_id == joint_id).unwrap_or(0);
+    let mut buffer = Buffer::default();
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut joints = Vec::new();
+    let mut weights = Vec::new();
+    let mut inverse_binds = Vec::new();
+    for (index, bone) in bones.iter().enumerate() {
+        let global = bind.global(bone.joint_id).unwrap_or(root);
+        inverse_binds.extend(global.inverse().to_cols_array());
+        let mesh = skeleton::make_bone_mesh(&bone.cube);
+        let bone_positions = attribute(&mesh, Mesh::ATTRIBUTE_POSITION);
+        for position in bone_positions.iter() {
+            positions.extend(global.transform_point3(Vec3::from(*position)).to_array());
+            joints.extend([index as u8, 0, 0, 0]);
+            weights.extend([1.0, 0.0, 0.0, 0.0]);
+        }
+        for normal in attribute(&mesh, Mesh::ATTRIBUTE_NORMAL) {
+            normals.extend(global.transform_vector3(Vec3::from(normal)).normalize().to_array());
+        }
+    }
+    let position = buffer.floats(&positions, "VEC3", Some(ARRAY_BUFFER), true);
+    let normal = buffer.floats(&normals, "VEC3", Some(ARRAY_BUFFER), false);
+    let joint = buffer.bytes(&joints, "VEC4", Some(ARRAY_BUFFER));
+    let weight = buffer.floats(&weights, "VEC4", Some(ARRAY_BUFFER), false);
+    let inverse_bind = buffer.floats(&inverse_binds, "MAT4", None, false);
+
+    let times = keys.iter().map(|(time, _asana_id, _joints)| *time).collect::<Vec<f32>>();
+    let input = buffer.floats(&times, "SCALAR", None, true);
+    let posed = keys
+        .iter()
+        .map(|(_time, asana_id, joints)| locals(bones, *asana_id, joints))
+        .collect::<Result<Vec<Vec<Transform>>, GltfError>>()?;
+    let mut samplers = Vec::new();
+    let mut channels = Vec::new();
+    for (index, bone) in bones.iter().enumerate() {
+        let mut rotations = Vec::new();
+        let mut translations = Vec::new();
+        let mut previous = Quat::IDENTITY;
+        for pose in posed.iter() {
+            // Keep neighbouring keys in the same hemisphere so viewers turn
+            // the short way round.
+            let rotation = pose[index].rotation;
+            let rotation = if rotation.dot(previous) < 0.0 { -rotation } else { rotation };
+            previous = rotation;
+            rotations.extend(rotation.to_array());
+            translations.extend(pose[index].translation.to_array());
+        }
+        for (path, values, kind) in [("rotation", rotations, "VEC4"), ("translation", translations, "VEC3")] {
+            let output = buffer.floats(&values, kind, None, false);
+            channels.push(json!({
+                "sampler": samplers.len(),
+                "target": { "node": node(bone.joint_id), "path": path },
+            }));
+            samplers.push(json!({ "input": input, "output": output, "interpolation": "LINEAR" }));
+        }
+    }
+
+    let mut nodes = vec![json!({
+        "name": "YogaMat",
+        "scale": [METRES, METRES, METRES],
+        "children": bones
+            .iter()
+            .filter(|bone| bone.parent.is_none())
+            .map(|bone| node(bone.joint_id))
+            .collect::<Vec<usize>>(),
+    })];
+    for (bone, local) in bones.iter().zip(rest.iter()) {
+        let mut bone_node = json!({
+            "name": bone.cube.name,
+            "translation": local.translation.to_array(),
+            "rotation": local.rotation.to_array(),
+        });
+        let children = bones
+            .iter()
+            .filter(|child| child.parent == Some(bone.joint_id))
+            .map(|child| node(child.joint_id))
+            .collect::<Vec<usize>>();
+        if !children.is_empty() {
+            bone_node["children"] = json!(children);
+        }
+        nodes.push(bone_node);
+    }
+    let mesh_node = nodes.len();
+    nodes.push(json!({ "name": "Bones", "mesh": 0, "skin": 0 }));
+
+    let colour = Color::srgb_u8(166, 116, 51).to_linear().to_f32_array();
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "YogaMat" },
+        "scene": 0,
+        "scenes": [{ "name": sequence.name, "nodes": [0, mesh_node] }],
+        "nodes": nodes,
+        "meshes": [{
+            "name": "Bones",
+            "primitives": [{
+                "attributes": {
+                    "POSITION": position,
+                    "NORMAL": normal,
+                    "JOINTS_0": joint,
+                    "WEIGHTS_0": weight,
+                },
+                "material": 0,
+            }],
+        }],
+        "materials": [{
+            "name": "Bone",
+            "pbrMetallicRoughness": {
+                "baseColorFactor": colour,
+                "metallicFactor": 0.0,
+                "roughnessFactor": 0.95,
+            },
+        }],
+        "skins": [{
+            "name": "Skeleton",
+            "inverseBindMatrices": inverse_bind,
+            "skeleton": node(bones[0].joint_id),
+            "joints": bones.iter().map(|bone| node(bone.joint_id)).collect::<Vec<usize>>(),
+        }],
+        "animations": [{ "name": sequence.name, "samplers": samplers, "channels": channels }],
+        "accessors": buffer.accessors,
+        "bufferViews": buffer.views,
+        "buffers": [{ "byteLength": buffer.bytes.len() }],
+    });
+    let json = serde_json::to_vec(&document).map_err(GltfError::Json)?;
+    Ok(glb(json, buffer.bytes))
+}
+
+/// The glTF binary container: a header, then the JSON and the binary buffer
+/// as chunks, each padded to 4 bytes.
+fn glb(mut json: Vec<u8>, mut bin: Vec<u8>) -> Vec<u8> {
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend(b"glTF");
+    glb.extend(2u32.to_le_bytes());
+    glb.extend((length as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(b"JSON");
+    glb.extend(json);
+    glb.extend((bin.len() as u32).to_le_bytes());
+    glb.extend(b"BIN\0");
+    glb.extend(bin);
+    glb
+}
@@ -0,0 +1,62 @@
+//! Resting a posed skeleton on the mat.
+//!
+//! Whatever the pose, the lowest vertex of the bone meshes goes onto the
+//! mat's top by moving the skeleton's root, so seated, supine and inverted
+//! poses neither float nor sink. The root keeps its rotation and scale.
+
+use bevy::{
+    math::{Mat4, Vec3},
+    transform::components::Transform,
+};
+
+use crate::{
+    kinematics::PosedSkeleton,
+    skeleton::{self, SkeletonBone},
+};
+
+/// Middle of the mat and its size.
+pub const MAT_CENTRE: Vec3 = Vec3::new(0.0, -109.5, 0.0);
+pub const MAT_SIZE: Vec3 = Vec3::new(57.35 * 2.0, 0.5 * 2.0, 21.7 * 2.0);
+
+/// Height of the mat's top surface.
+pub const fn mat_top() -> f32 {
+    MAT_CENTRE.y + MAT_SIZE.y / 2.0
+}
+
+/// Smallest and largest corner of the box around the bone meshes of `bones`,
+/// placed by `posed` and then by `root`. `None` when no bone is placed.
+pub fn bounds(bones: &[SkeletonBone], posed: &PosedSkeleton, root: Mat4) -> Option<(Vec3, Vec3)> {
+    bones
+        .iter()
+        .filter_map(|bone| Some((bone, posed.global(bone.joint_id)?)))
+        .flat_map(|(bone, global)| {
+            let placed = root * global;
+            skeleton::bone_corners(&bone.cube)
+                .into_iter()
+                .map(move |corner| placed.transform_point3(Vec3::from(corner)))
+        })
+        .fold(None, |bounds, point| match bounds {
+            None => Some((point, point)),
+            Some((min, max)) => Some((min.min(point), max.max(point))),
+        })
+}
+
+/// `root`, the transform of a skeleton's root, moved so the skeleton posed
+/// as `posed` (relative to its root) rests on the mat. With `centre` the box
+/// around the body is centred over that point in x and z, otherwise the
+/// root keeps its x and z.
+pub fn ground(bones: &[SkeletonBone], posed: &PosedSkeleton, root: Transform, centre: Option<Vec3>) -> Transform {
+    let turned = Transform {
+        translation: Vec3::ZERO,
+        ..root
+    };
+    let Some((min, max)) = bounds(bones, posed, turned.compute_matrix()) else {
+        return root;
+    };
+    let middle = (min + max) / 2.0;
+    let translation = match centre {
+        Some(centre) => Vec3::new(centre.x - middle.x, mat_top() - min.y, centre.z - middle.z),
+        None => Vec3::new(root.translation.x, mat_top() - min.y, root.translation.z),
+    };
+    Transform { translation, ..root }
+}
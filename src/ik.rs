@@ -0,0 +1,185 @@
+//! Inverse kinematics for the limbs: turning the joints of a chain so its
+//! last joint reaches a target.
+//!
+//! Chains are solved with cyclic coordinate descent. Each pass first turns
+//! the upper limb about the line to the target so the elbow or knee points
+//! at the pole, and bends the elbow or knee about its hinge so the limb is
+//! as long as the target is far. Then every other joint, from the end of the
+//! chain back, turns so the end swings towards the target. Without the
+//! bending, a straight knee that can't overextend would never start to bend.
+//! Every joint is clamped to its range of motion after it turns.
+
+use std::{f32::consts::PI, sync::LazyLock};
+
+use bevy::prelude::*;
+
+use crate::{limits, skeleton};
+
+/// Passes before giving up on a target out of reach.
+const ITERATIONS: usize = 20;
+/// Distance to the target, in skeleton units, that counts as reached.
+const TOLERANCE: f32 = 0.01;
+
+/// A limb, from the joint that moves it to the one that reaches.
+pub struct Chain {
+    pub name: &'static str,
+    /// Joint ids, parents first. The last one is placed on the target.
+    pub joints: Vec<i32>,
+    /// Way the elbow or knee points, in the frame of the bone above it.
+    pub bend: Vec3,
+}
+
+impl Chain {
+    /// The limb ending in the bone of `SKELETON` named `end`: it and its
+    /// ancestors up to the first bone down the middle of the body.
+    fn limb(name: &'static str, end: &str, bend: Vec3) -> Chain {
+        let end = skeleton::find_bone_by_name(end)
+            .unwrap_or_else(|| panic!("the skeleton has no bone named {end}"));
+        let mut joints = skeleton::ancestors(end.joint_id)
+            .into_iter()
+            .take_while(|joint_id| skeleton::opposite(*joint_id) != *joint_id)
+            .collect::<Vec<i32>>();
+        joints.reverse();
+        Chain { name, joints, bend }
+    }
+}
+
+pub static CHAINS: LazyLock<[Chain; 4]> = LazyLock::new(|| {
+    [
+        Chain::limb("Left hand", "Left Hand", Vec3::NEG_Z),
+        Chain::limb("Right hand", "Right Hand", Vec3::NEG_Z),
+        Chain::limb("Left foot", "Left Foot", Vec3::Z),
+        Chain::limb("Right foot", "Right Foot", Vec3::Z),
+    ]
+});
+
+/// Where a chain should end up, in the space of the chain's parent's
+/// `Transform`s, the skeleton root for the bones.
+#[derive(Debug, Clone, Copy)]
+pub struct Goal {
+    /// Where the origin of the last joint goes.
+    pub position: Vec3,
+    /// Rotation the last joint should have, it keeps its own otherwise.
+    pub rotation: Option<Quat>,
+    /// A point the middle joint, elbow or knee, should bend towards.
+    pub pole: Option<Vec3>,
+}
+
+/// The joints of a chain placed by their parent-relative `locals`, under a
+/// parent at `parent`.
+pub fn globals(parent: Transform, locals: &[Transform]) -> Vec<Transform> {
+    let mut globals: Vec<Transform> = Vec::with_capacity(locals.len());
+    for local in locals {
+        let above = globals.last().copied().unwrap_or(parent);
+        globals.push(above.mul_transform(*local));
+    }
+    globals
+}
+
+impl Chain {
+    /// A pole the way the elbow or knee points with the upper limb turned as
+    /// it is, as far out from it as the upper limb is long. `None` for a
+    /// chain without a middle joint.
+    pub fn pole(&self, parent: Transform, locals: &[Transform]) -> Option<Vec3> {
+        let globals = globals(parent, locals);
+        let [upper, middle, _end] = globals.get(globals.len().checked_sub(3)?..)? else {
+            return None;
+        };
+        let length = middle.translation.distance(upper.translation);
+        Some(middle.translation + upper.rotation * self.bend * length)
+    }
+
+    /// Axis the elbow or knee bends about, in the frame of the bone above
+    /// it. Turning about it bends the limb further.
+    fn hinge(&self) -> Vec3 {
+        self.bend.cross(Vec3::NEG_Y)
+    }
+}
+
+/// Turns joint `index` so its global rotation becomes `turn` times what it
+/// was, within its range of motion.
+fn turn(
+    chain: &Chain,
+    parent: Transform,
+    locals: &mut [Transform],
+    globals: &[Transform],
+    index: usize,
+    by: Quat,
+) {
+    let above = if index == 0 {
+        parent.rotation
+    } else {
+        globals[index - 1].rotation
+    };
+    let rotation = above.inverse() * by * globals[index].rotation;
+    locals[index].rotation = limits::range_of_motion(chain.joints[index]).clamp(rotation.normalize());
+}
+
+/// Bends the middle of the three joints from `upper` about its hinge so the
+/// end of the chain is as far from `upper` as the target is.
+fn bend_to_reach(chain: &Chain, parent: Transform, locals: &mut [Transform], upper: usize, goal: &Goal) {
+    let globals = globals(parent, locals);
+    let root = globals[upper].translation;
+    let middle = globals[upper + 1].translation;
+    let end = globals[upper + 2].translation;
+    let (to_middle, to_end) = (middle - root, end - middle);
+    let (a, b) = (to_middle.length(), to_end.length());
+    let hinge = globals[upper].rotation * chain.hinge();
+    // Law of cosines: the angle at the middle joint for a given reach.
+    let inside = |reach: f32| ((a * a + b * b - reach * reach) / (2.0 * a * b)).clamp(-1.0, 1.0).acos();
+    let reach = goal.position.distance(root).clamp((a - b).abs(), a + b);
+    let bent = to_middle.cross(to_end).dot(hinge).atan2(to_middle.dot(to_end));
+    let by = PI - inside(reach) - bent;
+    turn(chain, parent, locals, &globals, upper + 1, Quat::from_axis_angle(hinge, by));
+}
+
+/// Turns the joints of `chain`, whose parent-relative transforms are
+/// `locals`, towards `goal`. Only rotations change. Returns how far the end
+/// of the chain is left from the target.
+pub fn solve(chain: &Chain, parent: Transform, locals: &mut [Transform], goal: &Goal) -> f32 {
+    let last = locals.len() - 1;
+    let upper = last.checked_sub(2);
+    let distance = |locals: &[Transform]| {
+        globals(parent, locals)[last].translation.distance(goal.position)
+    };
+    for _ in 0..ITERATIONS {
+        if let Some(upper) = upper {
+            if let Some(pole) = goal.pole {
+                let globals = globals(parent, locals);
+                let root = globals[upper].translation;
+                let axis = goal.position - root;
+                let bend = (globals[upper].rotation * chain.bend).reject_from(axis).try_normalize();
+                let wanted = (pole - root).reject_from(axis).try_normalize();
+                if let (Some(bend), Some(wanted)) = (bend, wanted) {
+                    turn(chain, parent, locals, &globals, upper, Quat::from_rotation_arc(bend, wanted));
+                }
+            }
+            bend_to_reach(chain, parent, locals, upper, goal);
+        }
+        // The middle joint only bends, swinging it would twist the hinge.
+        let middle = upper.map(|upper| upper + 1);
+        for index in (0..last).rev().filter(|index| Some(*index) != middle) {
+            let globals = globals(parent, locals);
+            let joint = globals[index].translation;
+            let to_end = (globals[last].translation - joint).try_normalize();
+            let to_target = (goal.position - joint).try_normalize();
+            if let (Some(to_end), Some(to_target)) = (to_end, to_target) {
+                turn(chain, parent, locals, &globals, index, Quat::from_rotation_arc(to_end, to_target));
+            }
+        }
+        if distance(locals) < TOLERANCE {
+            break;
+        }
+    }
+    if let Some(rotation) = goal.rotation {
+        let globals = globals(parent, locals);
+        let above = if last == 0 {
+            parent.rotation
+        } else {
+            globals[last - 1].rotation
+        };
+        locals[last].rotation =
+            limits::range_of_motion(chain.joints[last]).clamp((above.inverse() * rotation).normalize());
+    }
+    distance(locals)
+}
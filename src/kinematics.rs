@@ -0,0 +1,100 @@
+//! Forward kinematics without Bevy's transform propagation: where every bone
+//! of a posed skeleton ends up.
+//!
+//! A joint's matrix places its bone relative to the parent bone, so a bone's
+//! global matrix is its parent's times its own, from the hips down. The
+//! local matrices go through `Transform` like the bone entities' do, so the
+//! result is what the app draws.
+
+use std::fmt;
+
+use bevy::{
+    math::{Mat4, Vec3},
+    transform::components::Transform,
+};
+
+use crate::skeleton::{Joint, SkeletonBone};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PosedBone {
+    pub joint_id: i32,
+    /// Placement of the bone in the space `forward_kinematics` was given.
+    pub global: Mat4,
+    /// The joint the bone turns about.
+    pub start: Vec3,
+    /// The far end of the bone's cube, the fingertips for a hand.
+    pub end: Vec3,
+}
+
+/// A skeleton with every bone placed, in the order of the bones it was
+/// posed from, parents first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PosedSkeleton {
+    pub bones: Vec<PosedBone>,
+}
+
+impl PosedSkeleton {
+    pub fn bone(&self, joint_id: i32) -> Option<&PosedBone> {
+        self.bones.iter().find(|bone| bone.joint_id == joint_id)
+    }
+
+    pub fn global(&self, joint_id: i32) -> Option<Mat4> {
+        self.bone(joint_id).map(|bone| bone.global)
+    }
+}
+
+/// A bone of the skeleton the pose has no joint for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingJoint(pub i32);
+
+impl fmt::Display for MissingJoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "joint {} is missing", self.0)
+    }
+}
+
+impl std::error::Error for MissingJoint {}
+
+/// Places `bones`, such as `skeleton::bones()` or a body's
+/// `BodyProportions::bones()`, by the parent-relative `matrices` of their
+/// joints. `root` places the hips' parent, `Mat4::IDENTITY` for skeleton
+/// space.
+pub fn pose_skeleton(
+    bones: &[SkeletonBone],
+    root: Mat4,
+    matrices: &[(i32, Mat4)],
+) -> Result<PosedSkeleton, MissingJoint> {
+    let mut posed: Vec<PosedBone> = Vec::with_capacity(bones.len());
+    for bone in bones {
+        let (_joint_id, mat) = matrices
+            .iter()
+            .find(|(joint_id, _mat)| *joint_id == bone.joint_id)
+            .ok_or(MissingJoint(bone.joint_id))?;
+        let parent = bone
+            .parent
+            .and_then(|parent| posed.iter().find(|placed| placed.joint_id == parent))
+            .map_or(root, |placed| placed.global);
+        let global = parent * Transform::from_matrix(*mat).compute_matrix();
+        let end = bone.cube.transform.translation - Vec3::Y * bone.cube.y / 2.0;
+        posed.push(PosedBone {
+            joint_id: bone.joint_id,
+            global,
+            start: global.w_axis.truncate(),
+            end: global.transform_point3(end),
+        });
+    }
+    Ok(PosedSkeleton { bones: posed })
+}
+
+/// `pose_skeleton` for the joints of a pose as they're stored.
+pub fn forward_kinematics(
+    bones: &[SkeletonBone],
+    root: Mat4,
+    joints: &[Joint],
+) -> Result<PosedSkeleton, MissingJoint> {
+    let matrices = joints
+        .iter()
+        .map(|joint| (joint.joint_id, joint.matrix()))
+        .collect::<Vec<(i32, Mat4)>>();
+    pose_skeleton(bones, root, &matrices)
+}
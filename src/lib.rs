@@ -1,25 +1,70 @@
+pub mod balance;
+pub mod body_mesh;
+pub mod collision;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod db;
+pub mod dristi;
+pub mod format;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gltf;
+pub mod grounding;
+pub mod ik;
+pub mod kinematics;
+pub mod limits;
+pub mod loader;
+pub mod mirror;
 pub mod picking;
+pub mod pose;
+pub mod proportions;
+pub mod sequence;
 pub mod skeleton;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod text;
+pub mod timeline;
+pub mod transition;
+pub mod validate;
 pub mod vector_ops;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use bincode::{Decode, Encode};
+use dristi::Dristi;
 use skeleton::Joint;
 
-#[derive(Debug, Serialize, Deserialize, Decode, Encode)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Decode, Encode)]
 pub struct Asana {
     pub asana_id: i32,
     pub pose_id: i32,
     pub sanskrit: String,
     pub english: String,
+    /// Sanskrit name with diacritics, from asanas_dristi.csv.
+    pub unicode: Option<String>,
     pub notes: Option<String>,
+    pub category_ids: Vec<i32>,
+    pub dristi: Option<Dristi>,
 }
 
-#[derive(Serialize, Deserialize, Decode, Encode)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Decode, Encode)]
+pub struct Category {
+    pub category_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Decode, Encode)]
 pub struct AsanaData {
     pub asanas: Vec<Asana>,
+    #[serde(serialize_with = "ordered_poses")]
     pub poses: HashMap<i32, Vec<Joint>>,
+    pub categories: Vec<Category>,
+}
+
+/// Keeps text exports stable from run to run.
+fn ordered_poses<S: Serializer>(
+    poses: &HashMap<i32, Vec<Joint>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    poses.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
 }
 
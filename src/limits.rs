@@ -0,0 +1,210 @@
+//! Anatomical range of motion of each joint, so implausible poses can be
+//! found and edits kept within reach.
+//!
+//! A rotation is measured from the joint's neutral rotation as a swing of
+//! the bone followed by a twist about it. The swing is split into its x part
+//! (flexion and extension for most joints) and its z part (abduction and
+//! adduction); the twist about y is the bone's rotation. The limits of a
+//! left joint mirror onto the right one by negating y and z.
+
+use std::fmt;
+
+use bevy::math::{Quat, Vec2, Vec3};
+
+use crate::{
+    skeleton::{self, Joint},
+    AsanaData,
+};
+
+/// How far past a limit, in degrees, still counts as within it. A rotation
+/// `clamp`ed onto a limit reads back a few thousandths of a degree either
+/// side of it, and edits and IK clamp every frame.
+const TOLERANCE: f32 = 0.01;
+
+/// What the x, y and z parts of a motion are called.
+pub const MOTIONS: [&str; 3] = ["flexion", "rotation", "abduction"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeOfMotion {
+    /// Parent-relative Euler angles in degrees, in `skeleton::EULER_ORDER`,
+    /// that the motion is measured from.
+    pub neutral: Vec3,
+    /// Smallest and largest flexion, rotation and abduction in degrees.
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+const fn range(neutral: [f32; 3], min: [f32; 3], max: [f32; 3]) -> RangeOfMotion {
+    RangeOfMotion {
+        neutral: Vec3::from_array(neutral),
+        min: Vec3::from_array(min),
+        max: Vec3::from_array(max),
+    }
+}
+
+/// The hips place the whole body, nothing limits them.
+pub const FREE: RangeOfMotion = range([0.0; 3], [-180.0; 3], [180.0; 3]);
+/// Negative flexion brings the thigh forward, positive abduction takes it
+/// out to the side. How far a flexed thigh turns can't be told apart from
+/// how far it swung, so its rotation isn't limited.
+pub const HIP: RangeOfMotion = range([0.0; 3], [-165.0, -180.0, -90.0], [60.0, 180.0, 165.0]);
+/// Flexion brings the heel back.
+pub const KNEE: RangeOfMotion = range([0.0; 3], [-10.0, -45.0, -30.0], [175.0, 45.0, 30.0]);
+/// From the foot at a right angle to the shin, flexion points the toes.
+pub const ANKLE: RangeOfMotion = range([-90.0, 0.0, 0.0], [-60.0, -40.0, -45.0], [100.0, 40.0, 45.0]);
+/// Per vertebra, the spine bends over several of them. L5 turns the spine
+/// up from the hips.
+pub const LUMBAR: RangeOfMotion = range([0.0, 0.0, 180.0], [-15.0, -5.0, -8.0], [15.0, 5.0, 8.0]);
+pub const THORACIC: RangeOfMotion = range([0.0; 3], [-8.0, -8.0, -6.0], [8.0, 8.0, 6.0]);
+pub const CERVICAL: RangeOfMotion = range([0.0; 3], [-15.0, -15.0, -10.0], [15.0, 15.0, 10.0]);
+/// Nodding, turning and tilting the head.
+pub const HEAD: RangeOfMotion = range([0.0; 3], [-45.0, -80.0, -40.0], [45.0, 80.0, 40.0]);
+/// From the clavical pointing out sideways.
+pub const CLAVICAL: RangeOfMotion = range([0.0, -14.0, -89.0], [-25.0, -10.0, -25.0], [25.0, 10.0, 25.0]);
+/// From the arm hanging by the side. Raising it overhead reaches 180° of
+/// abduction either way round, so only reaching back is limited.
+pub const SHOULDER: RangeOfMotion = range([0.0, 0.0, -85.0], [-180.0; 3], [170.0, 180.0, 180.0]);
+/// Bending the elbow is negative flexion. Like the hip, the forearm's turn
+/// and swing mix once the elbow is bent, so only flexion is limited.
+pub const ELBOW: RangeOfMotion = range([0.0; 3], [-160.0, -180.0, -180.0], [15.0, 180.0, 180.0]);
+/// Bending the wrist back and forth is abduction on this skeleton.
+pub const WRIST: RangeOfMotion = range([0.0; 3], [-40.0, -30.0, -95.0], [30.0, 30.0, 130.0]);
+
+impl RangeOfMotion {
+    /// The same joint on the other side of the body.
+    pub const fn mirrored(self) -> RangeOfMotion {
+        RangeOfMotion {
+            neutral: Vec3::new(self.neutral.x, -self.neutral.y, -self.neutral.z),
+            min: Vec3::new(self.min.x, -self.max.y, -self.max.z),
+            max: Vec3::new(self.max.x, -self.min.y, -self.min.z),
+        }
+    }
+
+    fn neutral_rotation(&self) -> Quat {
+        skeleton::rotation_from_degrees(self.neutral)
+    }
+
+    /// Flexion, rotation and abduction in degrees of a parent-relative
+    /// `rotation`, each within ±180°.
+    pub fn motion(&self, rotation: Quat) -> Vec3 {
+        let delta = self.neutral_rotation().inverse() * rotation;
+        // q and -q are the same rotation, the one with w >= 0 turns less.
+        let delta = if delta.w < 0.0 { -delta } else { delta };
+        let twist = Vec2::new(delta.y, delta.w)
+            .try_normalize()
+            .map_or(Quat::IDENTITY, |twist| Quat::from_xyzw(0.0, twist.x, 0.0, twist.y));
+        let swing = delta * twist.inverse();
+        let swing = if swing.w < 0.0 { -swing } else { swing };
+        let swing = swing.to_scaled_axis();
+        Vec3::new(swing.x, 2.0 * twist.y.atan2(twist.w), swing.z).map(f32::to_degrees)
+    }
+
+    /// Inverse of `motion`.
+    pub fn rotation(&self, motion: Vec3) -> Quat {
+        let motion = motion.map(f32::to_radians);
+        let swing = Quat::from_scaled_axis(Vec3::new(motion.x, 0.0, motion.z));
+        self.neutral_rotation() * swing * Quat::from_rotation_y(motion.y)
+    }
+
+    /// `rotation` brought within the limits.
+    pub fn clamp(&self, rotation: Quat) -> Quat {
+        let motion = self.motion(rotation);
+        let clamped = motion.clamp(self.min, self.max);
+        if clamped == motion {
+            rotation
+        } else {
+            self.rotation(clamped)
+        }
+    }
+
+    /// Each part of the motion of `rotation` that is out of range, as
+    /// (index into `MOTIONS`, angle, the limit it passed).
+    pub fn exceeded(&self, rotation: Quat) -> Vec<(usize, f32, f32)> {
+        let motion = self.motion(rotation).to_array();
+        (0..3)
+            .filter_map(|axis| {
+                let angle = motion[axis];
+                if angle < self.min[axis] - TOLERANCE {
+                    Some((axis, angle, self.min[axis]))
+                } else if angle > self.max[axis] + TOLERANCE {
+                    Some((axis, angle, self.max[axis]))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Range of motion of `joint_id`, `FREE` for joints the skeleton doesn't have.
+pub fn range_of_motion(joint_id: i32) -> RangeOfMotion {
+    skeleton::find_bone(joint_id).map_or(FREE, |bone| bone.rom)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub joint_id: i32,
+    /// Index into `MOTIONS`.
+    pub motion: usize,
+    pub angle: f32,
+    pub limit: f32,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = skeleton::find_bone(self.joint_id)
+            .map(|bone| bone.cube.name.clone())
+            .unwrap_or_else(|| format!("joint {}", self.joint_id));
+        write!(
+            f,
+            "{name} {} {:.0}° is past {:.0}°",
+            MOTIONS[self.motion], self.angle, self.limit
+        )
+    }
+}
+
+/// Where the parent-relative `rotation` of `joint_id` is out of range.
+pub fn joint_violations(joint_id: i32, rotation: Quat) -> Vec<Violation> {
+    range_of_motion(joint_id)
+        .exceeded(rotation)
+        .into_iter()
+        .map(|(motion, angle, limit)| Violation {
+            joint_id,
+            motion,
+            angle,
+            limit,
+        })
+        .collect()
+}
+
+pub fn pose_violations(joints: &[Joint]) -> Vec<Violation> {
+    let mut violations = joints
+        .iter()
+        .flat_map(|joint| joint_violations(joint.joint_id, joint.rotation()))
+        .collect::<Vec<Violation>>();
+    violations.sort_by_key(|violation| violation.joint_id);
+    violations
+}
+
+/// The joints of one asana that are out of range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsanaViolations {
+    pub asana_id: i32,
+    pub sanskrit: String,
+    pub violations: Vec<Violation>,
+}
+
+/// Every asana with a joint out of range, in the order of `data.asanas`.
+pub fn check(data: &AsanaData) -> Vec<AsanaViolations> {
+    data.asanas
+        .iter()
+        .filter_map(|asana| {
+            let violations = pose_violations(data.poses.get(&asana.pose_id)?);
+            (!violations.is_empty()).then(|| AsanaViolations {
+                asana_id: asana.asana_id,
+                sanskrit: asana.sanskrit.clone(),
+                violations,
+            })
+        })
+        .collect()
+}
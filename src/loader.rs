@@ -0,0 +1,129 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{
+        io::{AssetSource, Reader},
+        AssetLoader, AssetPath, LoadContext,
+    },
+    prelude::*,
+};
+
+use crate::{format, AsanaData};
+
+/// Asset source the pose database is loaded from, see `register_source`.
+pub const POSE_DATABASE_SOURCE: &str = "pose_database";
+
+/// An out_db loaded through the `AssetServer`.
+#[derive(Asset, TypePath)]
+pub struct PoseDatabase(pub AsanaData);
+
+#[derive(Debug)]
+pub enum PoseDatabaseLoaderError {
+    Io(std::io::Error),
+    Format(format::FormatError),
+}
+
+impl fmt::Display for PoseDatabaseLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoseDatabaseLoaderError::Io(e) => write!(f, "couldn't read pose database: {e}"),
+            PoseDatabaseLoaderError::Format(e) => write!(f, "bad pose database: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PoseDatabaseLoaderError {}
+
+impl From<std::io::Error> for PoseDatabaseLoaderError {
+    fn from(e: std::io::Error) -> Self {
+        PoseDatabaseLoaderError::Io(e)
+    }
+}
+
+impl From<format::FormatError> for PoseDatabaseLoaderError {
+    fn from(e: format::FormatError) -> Self {
+        PoseDatabaseLoaderError::Format(e)
+    }
+}
+
+#[derive(Default)]
+pub struct PoseDatabaseLoader;
+
+impl AssetLoader for PoseDatabaseLoader {
+    type Asset = PoseDatabase;
+    type Settings = ();
+    type Error = PoseDatabaseLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(PoseDatabase(format::decode(&bytes)?))
+    }
+}
+
+/// Loads out_db files as `PoseDatabase` assets. out_db has no extension so
+/// load it with an explicit type, `asset_server.load::<PoseDatabase>(path)`.
+pub struct PoseDatabasePlugin;
+
+impl Plugin for PoseDatabasePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<PoseDatabase>()
+            .init_asset_loader::<PoseDatabaseLoader>();
+    }
+}
+
+/// Points the `pose_database://` asset source at the directory holding
+/// `path` and returns the asset path of the file in it. Natively `path` is
+/// on the filesystem, on wasm it is fetched relative to the page.
+///
+/// Asset sources have to exist before `AssetPlugin` is built, so call this
+/// before adding `DefaultPlugins`.
+pub fn register_source(app: &mut App, path: &Path) -> AssetPath<'static> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let path = std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf());
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let file_name = path
+        .file_name()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("out_db"));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let source = AssetSource::build().with_reader(move || {
+        Box::new(bevy::asset::io::file::FileAssetReader::new(dir.clone()))
+    });
+    #[cfg(target_arch = "wasm32")]
+    let source = AssetSource::build().with_reader(move || {
+        Box::new(bevy::asset::io::wasm::HttpWasmAssetReader::new(dir.clone()))
+    });
+    app.register_asset_source(POSE_DATABASE_SOURCE, source);
+
+    AssetPath::from_path(&file_name)
+        .with_source(POSE_DATABASE_SOURCE)
+        .into_owned()
+}
+
+/// Pose database to load natively: the path after `--db` in the command
+/// line `args`, then `env`, the YOGAMAT_DB environment variable, then
+/// ./out_db. The path is either an out_db file or a directory written by
+/// `database export --to tree`.
+pub fn database_path(args: impl IntoIterator<Item = String>, env: Option<String>) -> PathBuf {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--db" {
+            if let Some(path) = args.next() {
+                return PathBuf::from(path);
+            }
+        }
+    }
+    env.map(PathBuf::from).unwrap_or_else(|| PathBuf::from("out_db"))
+}
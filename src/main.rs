@@ -1,16 +1,35 @@
+use bevy::asset::{AssetLoadFailedEvent, AssetPath};
 use bevy::pbr::NotShadowCaster;
+use bevy::render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy::render::view::NoFrustumCulling;
 use bevy::{prelude::*, window::WindowResolution};
 use bevy_egui::{EguiContexts, EguiPlugin, EguiPrimaryContextPass};
 use bevy_mod_outline::{ComputedOutline, OutlineMode, OutlineStencil, OutlineVolume};
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-use yogamat_wasm::AsanaData;
-use std::collections::BTreeMap;
+use yogamat_wasm::{
+    balance::{self, Balance},
+    body_mesh::{self, BodyMesh},
+    collision::{self, Collision},
+    dristi::Dristi,
+    format, grounding, ik, kinematics, limits, mirror, pose,
+    proportions::BodyProportions,
+    sequence::{self, Pacing, Phase, Player, Sequence},
+    timeline::{Keyframe, Timeline},
+    transition::{self, Transition},
+    validate, AsanaData,
+};
+use yogamat_wasm::loader::{self, PoseDatabase, PoseDatabasePlugin};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::f32::consts::FRAC_PI_2;
+use std::path::PathBuf;
 use transform_gizmo_bevy::{
-    enum_set, GizmoCamera, GizmoMode, GizmoOptions, TransformGizmoPlugin,
+    enum_set, GizmoCamera, GizmoMode, GizmoOptions, GizmoTarget, TransformGizmoPlugin,
 };
 use yogamat_wasm::picking::{GizmoPickingPlugin, PickSelection};
-use yogamat_wasm::skeleton::{self, make_bone_mesh, BoneCube, JointMatrix};
+use yogamat_wasm::skeleton::{self, make_bone_mesh, BoneCube, Joint, SkeletonBone};
+#[cfg(not(target_arch = "wasm32"))]
+use yogamat_wasm::{db, text};
 
 #[derive(Component)]
 struct MainMenu;
@@ -18,12 +37,26 @@ struct MainMenu;
 #[derive(Component)]
 struct AsanaName;
 
+/// Ring beside the asana name that grows on an inhale and shrinks on an
+/// exhale while a sequence is paced by breath.
+#[derive(Component)]
+struct BreathRing;
+
+/// "Inhale" or "Exhale" beside the breath ring.
+#[derive(Component)]
+struct BreathText;
+
+/// Sizes of the breath ring, in pixels, breathed out and in.
+const BREATH_RING: (f32, f32) = (12.0, 40.0);
+
 #[derive(Component)]
 struct ResetViewButton;
 
+#[allow(dead_code)]
 #[derive(Component)]
 struct UpButton;
 
+#[allow(dead_code)]
 #[derive(Component)]
 struct DownButton;
 
@@ -36,6 +69,59 @@ pub struct YogaAssets {
     asana_name_entry: String,
     check_sanskrit: bool,
     possible_asanas: Vec<usize>,
+    selected_categories: Vec<i32>,
+    database_error: Option<String>,
+    /// Why `set_pose` couldn't pose a skeleton, cleared once it can.
+    pose_error: Option<String>,
+    /// What the last "Save pose" wrote, or why it failed.
+    save_status: Option<Result<String, String>>,
+    /// Bone of the current skeleton whose angles the side panel shows.
+    angle_joint: i32,
+    /// Body the current skeleton is built with, the others keep the default.
+    body: BodyProportions,
+    /// Show the skinned bodies instead of the bone cubes.
+    smooth_body: bool,
+    /// Keep edited joints within their range of motion.
+    clamp_to_limits: bool,
+    /// Show the handles that pose the hands and feet by inverse kinematics.
+    ik: bool,
+    /// Pose the current skeleton with the selected asana mirrored.
+    other_side: bool,
+    /// The sequence being played wants its step on the other side, which
+    /// `other_side` flips again. Cleared once an asana is picked by hand.
+    sequence_side: bool,
+    /// Move every skeleton down or up onto the mat.
+    ground: bool,
+    /// While grounded, also centre each body on where its skeleton was
+    /// spawned, the middle of the mat for the first one.
+    centre: bool,
+    /// Draw the current skeleton's centre of mass and support polygon.
+    balance: bool,
+    /// How long `set_pose` takes to move the bones to a new pose, in
+    /// seconds. They jump straight there at 0.
+    transition_seconds: f32,
+    easing: EaseFunction,
+    /// The bones on their way to the pose `set_pose` last set.
+    transition: Option<Transition>,
+}
+
+/// The out_db being loaded at runtime, it replaces the embedded copy once loaded.
+#[derive(Resource)]
+struct PoseDatabaseHandle(Handle<PoseDatabase>);
+
+/// Where the runtime pose database comes from.
+#[derive(Resource)]
+enum PoseDatabaseSource {
+    /// An out_db, loaded through the asset server.
+    Asset {
+        path: AssetPath<'static>,
+        /// Where "Save pose" writes it back.
+        #[cfg(not(target_arch = "wasm32"))]
+        file: PathBuf,
+    },
+    /// A directory of per-asana JSON files, read at startup.
+    #[cfg(not(target_arch = "wasm32"))]
+    Tree(PathBuf),
 }
 
 #[derive(Component)]
@@ -47,29 +133,134 @@ struct Bone {
 #[derive(Component)]
 struct Skeleton {
     id: i32,
+    /// Where the skeleton was spawned, the root goes back there when it
+    /// isn't resting on the mat.
+    home: Transform,
+}
+
+/// Bones of the current skeleton's body, rebuilt by `apply_body` when the
+/// body changes.
+#[derive(Resource, Deref)]
+struct BodyBones(Vec<SkeletonBone>);
+
+impl Default for BodyBones {
+    fn default() -> Self {
+        BodyBones(BodyProportions::default().bones())
+    }
+}
+
+/// Balance of the current skeleton, while it's shown.
+#[derive(Resource, Default)]
+struct CurrentBalance(Option<Balance>);
+
+/// The sequences the side panel plays on the current skeleton.
+#[derive(Resource)]
+struct SequencePlayer {
+    sequences: Vec<Sequence>,
+    player: Player,
+    /// Step the panel skipped to, for `play_sequence` to pose.
+    skipped_to: Option<usize>,
+}
+
+impl Default for SequencePlayer {
+    fn default() -> Self {
+        let sequences = sequence::built_in();
+        SequencePlayer {
+            player: Player::new(sequences[0].clone()),
+            sequences,
+            skipped_to: None,
+        }
+    }
+}
+
+/// The timeline authored in the side panel and played on the current
+/// skeleton.
+#[derive(Resource)]
+struct TimelineEditor {
+    timeline: Timeline,
+    /// Where the playhead is, in seconds.
+    time: f32,
+    playing: bool,
+    /// Pose the current skeleton at `time` on the next update.
+    scrubbed: bool,
+    /// Key whose time and easing the panel edits.
+    selected: Option<usize>,
+    /// Where Save and Load write and read the timeline.
+    #[cfg(not(target_arch = "wasm32"))]
+    path: String,
+    error: Option<String>,
+}
+
+impl Default for TimelineEditor {
+    fn default() -> Self {
+        TimelineEditor {
+            timeline: Timeline::default(),
+            time: 0.0,
+            playing: false,
+            scrubbed: false,
+            selected: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            path: "timeline.json".to_string(),
+            error: None,
+        }
+    }
+}
+
+/// Bones passing through each other, by skeleton id.
+#[derive(Resource, Default)]
+struct SkeletonCollisions(HashMap<i32, Vec<Collision>>);
+
+/// Bone materials, swapped by `highlight_bones`.
+#[derive(Resource)]
+struct BoneMaterials {
+    bone: Handle<StandardMaterial>,
+    out_of_range: Handle<StandardMaterial>,
+    colliding: Handle<StandardMaterial>,
+}
+
+/// The smooth body of a skeleton, skinned to its bones.
+#[derive(Component)]
+struct BodySkin {
+    skeleton_id: i32,
+}
+
+/// Handle of the current skeleton that a limb of `ik::CHAINS` reaches for,
+/// or its pole, which the elbow or knee points at.
+#[derive(Component)]
+struct IkHandle {
+    chain: usize,
+    pole: bool,
 }
 
 #[derive(Component)]
 struct BoneAxis;
 
+/// The skeleton that `set_pose` poses with the selected asana.
+const CURRENT_SKELETON: i32 = 1;
+
+/// The other skeletons and the asana id each stands in: Tadasana, Virasana
+/// and Ardha Matsyendrasana.
+const OTHER_SKELETONS: [(i32, i32); 3] = [(0, pose::TADASANA), (2, 170), (3, 93)];
+
 impl YogaAssets {
     fn search(&mut self) {
         let matcher = SkimMatcherV2::default();
         let mut scores: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
 
-        for (i, asana_name) in self
-            .asanas
-            .asanas
-            .iter()
-            .map(|asana| {
-                if self.check_sanskrit {
-                    &asana.sanskrit
-                } else {
-                    &asana.english
-                }
-            })
-            .enumerate()
-        {
+        for (i, asana) in self.asanas.asanas.iter().enumerate() {
+            // every selected category narrows the list further
+            if !self
+                .selected_categories
+                .iter()
+                .all(|category_id| asana.category_ids.contains(category_id))
+            {
+                continue;
+            }
+            let asana_name = if self.check_sanskrit {
+                &asana.sanskrit
+            } else {
+                &asana.english
+            };
             if let Some((score, _indices_into_haystack)) =
                 matcher.fuzzy_indices(asana_name, &self.asana_name_entry.to_lowercase())
             {
@@ -85,6 +276,47 @@ impl YogaAssets {
             }
         }
     }
+
+    /// Whether the current skeleton shows its asana mirrored.
+    fn mirrored(&self) -> bool {
+        self.other_side != self.sequence_side
+    }
+
+    fn replace_asanas(&mut self, asanas: AsanaData) {
+        check_database(&asanas);
+        self.asanas = asanas;
+        if self.current_idx >= self.asanas.asanas.len() {
+            self.current_idx = 0;
+        }
+        self.selected_categories.clear();
+        self.search();
+    }
+}
+
+/// Pose database to load at startup, see `loader::database_path`.
+#[cfg(not(target_arch = "wasm32"))]
+fn database_path() -> PathBuf {
+    loader::database_path(std::env::args().skip(1), std::env::var("YOGAMAT_DB").ok())
+}
+
+/// The web build fetches out_db from next to the page.
+#[cfg(target_arch = "wasm32")]
+fn database_path() -> PathBuf {
+    PathBuf::from("out_db")
+}
+
+/// Call before adding `DefaultPlugins`, see `loader::register_source`.
+fn database_source(app: &mut App) -> PoseDatabaseSource {
+    let path = database_path();
+    #[cfg(not(target_arch = "wasm32"))]
+    if path.is_dir() {
+        return PoseDatabaseSource::Tree(path);
+    }
+    PoseDatabaseSource::Asset {
+        path: loader::register_source(app, &path),
+        #[cfg(not(target_arch = "wasm32"))]
+        file: path,
+    }
 }
 
 fn main() {
@@ -97,7 +329,9 @@ fn main() {
     #[cfg(target_arch = "wasm32")]
     let height = 700.0;
 
-    App::new()
+    let mut app = App::new();
+    let database_source = database_source(&mut app);
+    app.insert_resource(database_source)
         .insert_resource(ClearColor(Color::Srgba(Srgba::hex("292929").unwrap())))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -109,6 +343,7 @@ fn main() {
             }),
             ..default()
         }))
+        .add_plugins(PoseDatabasePlugin)
         .add_plugins(EguiPlugin::default())
         .add_plugins(TransformGizmoPlugin)
         .add_plugins(GizmoPickingPlugin)
@@ -117,6 +352,8 @@ fn main() {
             Startup,
             (
                 spawn_main_skeleton,
+                spawn_body_skins,
+                spawn_ik_handles,
                 spawn_camera,
                 spawn_main_axis,
                 setup_ui,
@@ -124,9 +361,34 @@ fn main() {
                 //export_scene,
             ).chain(),
         )
+        .init_resource::<BodyBones>()
+        .init_resource::<CurrentBalance>()
+        .init_resource::<SkeletonCollisions>()
+        .init_resource::<SequencePlayer>()
+        .init_resource::<TimelineEditor>()
         .add_systems(PreStartup, load_resources)
         .add_systems(PostStartup, initial_pose)
-        .add_systems(Update, (keyboard_input_system, button_clicked))
+        .add_systems(
+            Update,
+            (
+                keyboard_input_system,
+                button_clicked,
+                draw_gaze,
+                draw_balance,
+                swap_in_database,
+                play_sequence,
+                show_breath,
+                animate_transition,
+                play_timeline,
+                apply_body,
+                show_body,
+                clamp_gizmo_edits,
+                (find_collisions, highlight_bones).chain(),
+                ground_skeletons,
+                show_ik_handles,
+                (solve_ik, place_ik_handles).chain(),
+            ),
+        )
         .add_systems(EguiPrimaryContextPass, pose_egui)
         .insert_resource(GizmoOptions {
             gizmo_modes: enum_set!(
@@ -147,17 +409,30 @@ fn main() {
 #[derive(Resource, Deref, DerefMut)]
 struct OriginalCameraTransform(Transform);
 
+#[allow(clippy::too_many_arguments)]
 fn pose_egui(
     mut contexts: EguiContexts,
     mut yoga_assets: ResMut<YogaAssets>,
-    bones: Query<(Entity, &mut Transform, &Bone)>,
+    mut bones: Query<(Entity, &mut Transform, &Bone)>,
     asana_text: Query<&mut Text, With<AsanaName>>,
+    names: Query<&Name>,
+    current_balance: Res<CurrentBalance>,
+    collisions: Res<SkeletonCollisions>,
+    mut sequence_player: ResMut<SequencePlayer>,
+    mut timeline_editor: ResMut<TimelineEditor>,
+    #[cfg(not(target_arch = "wasm32"))] database_source: Res<PoseDatabaseSource>,
 ) {
     let ctx = contexts.ctx_mut().unwrap();
     egui::SidePanel::left("left_panel")
         .resizable(true)
         .show(ctx, |ui| {
             ui.vertical(|ui| {
+                if let Some(error) = &yoga_assets.database_error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                if let Some(error) = &yoga_assets.pose_error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
                 if ui
                     .checkbox(&mut yoga_assets.check_sanskrit, "Search in Sanskrit")
                     .clicked()
@@ -169,6 +444,102 @@ fn pose_egui(
                 if response.changed() {
                     yoga_assets.search();
                 }
+                let mut toggled_category = None;
+                ui.horizontal_wrapped(|ui| {
+                    for category in yoga_assets.asanas.categories.iter().filter(|category| {
+                        yoga_assets
+                            .asanas
+                            .asanas
+                            .iter()
+                            .any(|asana| asana.category_ids.contains(&category.category_id))
+                    }) {
+                        let selected = yoga_assets
+                            .selected_categories
+                            .contains(&category.category_id);
+                        if ui.selectable_label(selected, &category.name).clicked() {
+                            toggled_category = Some(category.category_id);
+                        }
+                    }
+                });
+                if let Some(category_id) = toggled_category {
+                    let selected = &mut yoga_assets.selected_categories;
+                    if let Some(position) = selected.iter().position(|id| *id == category_id) {
+                        selected.remove(position);
+                    } else {
+                        selected.push(category_id);
+                    }
+                    yoga_assets.search();
+                }
+                // After a failed `set_pose` the bones still show the pose
+                // before, which would be saved over the selected asana. The
+                // built-in poses shown when the database couldn't be loaded
+                // aren't written over it either.
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    if ui
+                        .add_enabled(
+                            yoga_assets.transition.is_none()
+                                && yoga_assets.pose_error.is_none()
+                                && yoga_assets.database_error.is_none(),
+                            egui::Button::new("Save pose"),
+                        )
+                        .on_disabled_hover_text(
+                            "Waits for the bones to reach a pose that could be shown, from a database that loaded",
+                        )
+                        .clicked()
+                    {
+                        save_pose(&mut yoga_assets, &database_source, &bones);
+                    }
+                    match &yoga_assets.save_status {
+                        Some(Ok(saved)) => {
+                            ui.colored_label(egui::Color32::LIGHT_GREEN, saved);
+                        }
+                        Some(Err(error)) => {
+                            ui.colored_label(egui::Color32::LIGHT_RED, error);
+                        }
+                        None => {}
+                    }
+                }
+                let flipped = ui
+                    .checkbox(&mut yoga_assets.other_side, "Show other side")
+                    .changed();
+                ui.add(
+                    egui::Slider::new(&mut yoga_assets.transition_seconds, 0.0..=3.0)
+                        .text("Transition")
+                        .suffix(" s"),
+                );
+                egui::ComboBox::from_label("Easing")
+                    .selected_text(format!("{:?}", yoga_assets.easing))
+                    .show_ui(ui, |ui| {
+                        for easing in transition::EASINGS {
+                            ui.selectable_value(&mut yoga_assets.easing, easing, format!("{easing:?}"));
+                        }
+                    });
+                egui::CollapsingHeader::new("Sequence").show(ui, |ui| {
+                    sequence_ui(ui, &mut sequence_player, &yoga_assets.asanas);
+                });
+                egui::CollapsingHeader::new("Timeline").show(ui, |ui| {
+                    timeline_ui(ui, &mut timeline_editor, &yoga_assets, &bones);
+                });
+                ui.checkbox(&mut yoga_assets.ground, "Rest on the mat");
+                ui.add_enabled_ui(yoga_assets.ground, |ui| {
+                    ui.checkbox(&mut yoga_assets.centre, "Centre on the mat");
+                });
+                ui.checkbox(&mut yoga_assets.balance, "Balance");
+                if yoga_assets.balance {
+                    balance_ui(ui, &current_balance);
+                }
+                for collision in collisions.0.get(&CURRENT_SKELETON).into_iter().flatten() {
+                    ui.colored_label(egui::Color32::from_rgb(200, 140, 255), collision.to_string());
+                }
+                ui.checkbox(&mut yoga_assets.smooth_body, "Smooth body");
+                ui.checkbox(&mut yoga_assets.ik, "Inverse kinematics");
+                egui::CollapsingHeader::new("Body").show(ui, |ui| {
+                    body_ui(ui, &mut yoga_assets.body);
+                });
+                egui::CollapsingHeader::new("Joint angles").show(ui, |ui| {
+                    joint_angles_ui(ui, &mut yoga_assets, &mut bones, &names);
+                });
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     let initial_idx = yoga_assets.current_idx;
                     let mut current_idx = yoga_assets.current_idx;
@@ -181,63 +552,1032 @@ fn pose_egui(
                         }
                     }
                     if initial_idx != current_idx {
+                        yoga_assets.sequence_side = false;
+                    }
+                    if initial_idx != current_idx || flipped {
                         yoga_assets.current_idx = current_idx;
-                        set_pose(yoga_assets, bones, asana_text);
+                        set_pose(yoga_assets, bones, asana_text, true);
                     }
                 });
             });
         });
 }
 
+fn sequence_ui(ui: &mut egui::Ui, sequence_player: &mut SequencePlayer, asanas: &AsanaData) {
+    let current_name = sequence_player.player.sequence().name.clone();
+    let mut selected = None;
+    egui::ComboBox::from_label("Flow")
+        .selected_text(current_name.as_str())
+        .show_ui(ui, |ui| {
+            for sequence in sequence_player.sequences.iter() {
+                if ui
+                    .selectable_label(sequence.name == current_name, sequence.name.as_str())
+                    .clicked()
+                {
+                    selected = Some(sequence.clone());
+                }
+            }
+        });
+    if let Some(sequence) = selected.filter(|sequence| sequence.name != current_name) {
+        let pacing = sequence_player.player.pacing();
+        sequence_player.player = Player::new(sequence);
+        sequence_player.player.set_pacing(pacing);
+        sequence_player.skipped_to = None;
+    }
+
+    let player = &mut sequence_player.player;
+    let mut pacing = player.pacing();
+    let mut by_breath = matches!(pacing, Pacing::Breath { .. });
+    if ui.checkbox(&mut by_breath, "Pace by breath").changed() {
+        pacing = if by_breath {
+            Pacing::Breath { per_minute: 7.5 }
+        } else {
+            Pacing::Seconds
+        };
+    }
+    if let Pacing::Breath { per_minute } = &mut pacing {
+        ui.add(egui::Slider::new(per_minute, 3.0..=15.0).text("Breaths a minute"));
+    }
+    if pacing != player.pacing() {
+        player.set_pacing(pacing);
+    }
+    let mut skipped_to = None;
+    ui.horizontal(|ui| {
+        if ui.button("Previous").clicked() {
+            skipped_to = player.previous_step();
+        }
+        if player.is_playing() {
+            if ui.button("Pause").clicked() {
+                player.pause();
+            }
+        } else if ui.button("Play").clicked() {
+            skipped_to = player.play();
+        }
+        if ui.button("Next").clicked() {
+            skipped_to = player.next_step();
+        }
+    });
+    let steps = player.sequence().steps.len();
+    let name = player
+        .current()
+        .and_then(|step| asanas.asanas.iter().find(|asana| asana.asana_id == step.asana_id))
+        .map(|asana| asana.sanskrit.trim().to_string())
+        .unwrap_or_default();
+    ui.add(egui::ProgressBar::new(player.progress()).text(format!(
+        "{} of {steps}: {name}",
+        player.step() + 1
+    )));
+    if skipped_to.is_some() {
+        sequence_player.skipped_to = skipped_to;
+    }
+}
+
+/// Keys of the timeline, a playhead to scrub and the controls to add keys
+/// from the current skeleton or the selected asana.
+fn timeline_ui(
+    ui: &mut egui::Ui,
+    editor: &mut TimelineEditor,
+    yoga_assets: &YogaAssets,
+    bones: &Query<(Entity, &mut Transform, &Bone)>,
+) {
+    let duration = editor.timeline.duration();
+    let mut added = None;
+    ui.horizontal(|ui| {
+        if editor.playing {
+            if ui.button("Pause").clicked() {
+                editor.playing = false;
+            }
+        } else if ui
+            .add_enabled(!editor.timeline.keyframes.is_empty(), egui::Button::new("Play"))
+            .clicked()
+        {
+            if editor.time >= duration {
+                editor.time = 0.0;
+            }
+            editor.playing = true;
+        }
+        if ui.button("Capture").clicked() {
+            let mut joints = bones
+                .iter()
+                .filter(|(_entity, _transform, bone)| bone.skeleton_id == CURRENT_SKELETON)
+                .map(|(_entity, transform, bone)| {
+                    let joint = Joint::from_matrix(bone.id, 0, &transform.compute_matrix());
+                    yoga_assets.body.to_default_body(&joint)
+                })
+                .collect::<Vec<Joint>>();
+            joints.sort_by_key(|joint| joint.joint_id);
+            added = Some(("Captured".to_string(), joints));
+        }
+        let asana = yoga_assets.asanas.asanas.get(yoga_assets.current_idx);
+        if ui
+            .add_enabled(asana.is_some(), egui::Button::new("Insert asana"))
+            .clicked()
+        {
+            added = asana.and_then(|asana| {
+                let joints = yoga_assets.asanas.poses.get(&asana.pose_id)?;
+                let joints = if yoga_assets.mirrored() {
+                    mirror::mirror_pose(joints)
+                } else {
+                    joints.clone()
+                };
+                Some((asana.sanskrit.trim().to_string(), joints))
+            });
+        }
+    });
+    if let Some((label, joints)) = added {
+        let index = editor.timeline.insert(Keyframe {
+            time: editor.time,
+            easing: yoga_assets.easing,
+            label,
+            joints,
+        });
+        editor.selected = Some(index);
+    }
+
+    // Room past the last key to place the next one.
+    let end = editor.timeline.duration() + 5.0;
+    if ui
+        .add(egui::Slider::new(&mut editor.time, 0.0..=end).text("Time").suffix(" s"))
+        .changed()
+    {
+        editor.playing = false;
+        editor.scrubbed = true;
+    }
+
+    let mut picked = None;
+    for (index, keyframe) in editor.timeline.keyframes.iter().enumerate() {
+        let text = format!("{:.2} s  {}", keyframe.time, keyframe.label);
+        if ui.selectable_label(editor.selected == Some(index), text).clicked() {
+            picked = Some(index);
+        }
+    }
+    if let Some(index) = picked {
+        editor.selected = Some(index);
+        editor.time = editor.timeline.keyframes[index].time;
+        editor.scrubbed = true;
+    }
+    if let Some(index) = editor.selected.filter(|index| *index < editor.timeline.keyframes.len()) {
+        ui.horizontal(|ui| {
+            let keyframe = &mut editor.timeline.keyframes[index];
+            egui::ComboBox::from_id_salt("keyframe easing")
+                .selected_text(format!("{:?}", keyframe.easing))
+                .show_ui(ui, |ui| {
+                    for easing in transition::EASINGS {
+                        ui.selectable_value(&mut keyframe.easing, easing, format!("{easing:?}"));
+                    }
+                });
+            let mut time = keyframe.time;
+            if ui
+                .add(egui::DragValue::new(&mut time).speed(0.05).range(0.0..=f32::MAX).suffix(" s"))
+                .changed()
+            {
+                match editor.timeline.retime(index, time) {
+                    Ok(index) => {
+                        editor.selected = Some(index);
+                        editor.scrubbed = true;
+                        editor.error = None;
+                    }
+                    Err(e) => editor.error = Some(e.to_string()),
+                }
+            } else if ui.button("Delete").clicked() {
+                editor.timeline.keyframes.remove(index);
+                editor.selected = None;
+                editor.scrubbed = true;
+            }
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut editor.path);
+            if ui.button("Save").clicked() {
+                editor.error = editor
+                    .timeline
+                    .write(std::path::Path::new(&editor.path))
+                    .err()
+                    .map(|e| e.to_string());
+            }
+            if ui.button("Load").clicked() {
+                match Timeline::read(std::path::Path::new(&editor.path)) {
+                    Ok(timeline) => {
+                        editor.timeline = timeline;
+                        editor.selected = None;
+                        editor.time = 0.0;
+                        editor.scrubbed = true;
+                        editor.error = None;
+                    }
+                    Err(e) => editor.error = Some(e.to_string()),
+                }
+            }
+        });
+    }
+    if let Some(error) = &editor.error {
+        ui.colored_label(egui::Color32::LIGHT_RED, error);
+    }
+}
+
+fn balance_ui(ui: &mut egui::Ui, current_balance: &CurrentBalance) {
+    match current_balance.0.as_ref().map(|balance| balance.margin) {
+        Some(Some(margin)) if margin >= 0.0 => {
+            ui.label(format!("Stability margin {margin:.1}"));
+        }
+        Some(Some(margin)) => {
+            ui.colored_label(
+                egui::Color32::LIGHT_RED,
+                format!("Centre of mass {:.1} outside the support", -margin),
+            );
+        }
+        Some(None) => {
+            ui.colored_label(egui::Color32::LIGHT_RED, "Nothing to stand on");
+        }
+        None => {}
+    }
+}
+
+/// Proportions of the current skeleton: a preset, then height and the
+/// segment ratios to adjust it.
+fn body_ui(ui: &mut egui::Ui, body: &mut BodyProportions) {
+    let preset = BodyProportions::PRESETS
+        .iter()
+        .find(|(_name, preset)| preset == body)
+        .map(|(name, _preset)| *name)
+        .unwrap_or("Custom");
+    egui::ComboBox::from_label("Preset")
+        .selected_text(preset)
+        .show_ui(ui, |ui| {
+            for (name, preset) in BodyProportions::PRESETS {
+                ui.selectable_value(body, preset, name);
+            }
+        });
+    ui.add(egui::Slider::new(&mut body.height, 45.0..=140.0).text("Height"));
+    for (label, ratio) in [
+        ("Head", &mut body.head),
+        ("Neck", &mut body.neck),
+        ("Torso", &mut body.torso),
+        ("Pelvis", &mut body.pelvis),
+        ("Shoulders", &mut body.shoulder),
+        ("Upper arm", &mut body.upper_arm),
+        ("Forearm", &mut body.forearm),
+        ("Hand", &mut body.hand),
+        ("Thigh", &mut body.thigh),
+        ("Shin", &mut body.shin),
+        ("Foot", &mut body.foot),
+    ] {
+        ui.add(egui::Slider::new(ratio, 0.5..=1.5).text(label));
+    }
+}
+
+/// Rebuilds the current skeleton's bones and body when its body changes,
+/// and moves its bones onto the new body without re-posing them.
+#[allow(clippy::too_many_arguments)]
+fn apply_body(
+    mut applied: Local<Option<BodyProportions>>,
+    yoga_assets: ResMut<YogaAssets>,
+    mut body_bones: ResMut<BodyBones>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut inverse_bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+    mut bone_meshes: Query<(&Bone, &mut Mesh3d), Without<BodySkin>>,
+    mut skins: Query<(&BodySkin, &mut Mesh3d, &mut SkinnedMesh)>,
+    mut bones: Query<(Entity, &mut Transform, &Bone)>,
+    asana_text: Query<&mut Text, With<AsanaName>>,
+) {
+    if *applied == Some(yoga_assets.body) {
+        return;
+    }
+    let previous = applied.replace(yoga_assets.body);
+    body_bones.0 = yoga_assets.body.bones();
+    for (bone, mut mesh) in bone_meshes.iter_mut() {
+        if bone.skeleton_id != CURRENT_SKELETON {
+            continue;
+        }
+        if let Some(body_bone) = body_bones.iter().find(|body_bone| body_bone.joint_id == bone.id) {
+            *mesh = Mesh3d(meshes.add(make_bone_mesh(&body_bone.cube)));
+        }
+    }
+    for (skin, mut mesh, mut skinned_mesh) in skins.iter_mut() {
+        if skin.skeleton_id != CURRENT_SKELETON {
+            continue;
+        }
+        if let Some(body) = make_body(&yoga_assets.asanas, &yoga_assets.body) {
+            *mesh = Mesh3d(meshes.add(body.mesh));
+            skinned_mesh.inverse_bindposes = inverse_bindposes.add(body.inverse_bindposes);
+        }
+    }
+    let Some(previous) = previous else {
+        set_pose(yoga_assets, bones, asana_text, false);
+        return;
+    };
+    // Carry the bones over as they are, keeping edits that aren't saved yet.
+    let body = yoga_assets.body;
+    let rebody = |joint_id: i32, transform: &Transform| {
+        let mat = previous.to_default_body_matrix(joint_id, transform.compute_matrix());
+        Transform::from_matrix(body.retarget_matrix(joint_id, mat))
+    };
+    for (_entity, mut transform, bone) in bones.iter_mut() {
+        if bone.skeleton_id == CURRENT_SKELETON {
+            *transform = rebody(bone.id, &transform);
+        }
+    }
+    if let Some(transition) = yoga_assets.into_inner().transition.as_mut() {
+        transition.replace(|entity, transform| match bones.get(entity) {
+            Ok((_entity, _transform, bone)) if bone.skeleton_id == CURRENT_SKELETON => rebody(bone.id, transform),
+            _ => *transform,
+        });
+    }
+}
+
+/// Swaps the bone cubes of every skeleton for its smooth body and back. A
+/// skeleton without a smooth body keeps its cubes.
+fn show_body(
+    mut shown: Local<Option<bool>>,
+    yoga_assets: Res<YogaAssets>,
+    added: Query<(), Added<BodySkin>>,
+    mut skins: Query<(&BodySkin, &mut Visibility)>,
+    mut bones: Query<(&Bone, &mut Visibility), Without<BodySkin>>,
+) {
+    if *shown == Some(yoga_assets.smooth_body) && added.is_empty() {
+        return;
+    }
+    *shown = Some(yoga_assets.smooth_body);
+    let (skin_visibility, bone_visibility) = if yoga_assets.smooth_body {
+        (Visibility::Inherited, Visibility::Hidden)
+    } else {
+        (Visibility::Hidden, Visibility::Inherited)
+    };
+    let mut skinned = Vec::new();
+    for (skin, mut visibility) in skins.iter_mut() {
+        *visibility = skin_visibility;
+        skinned.push(skin.skeleton_id);
+    }
+    // Hiding the hips hides every bone below them.
+    for (bone, mut visibility) in bones.iter_mut() {
+        if skeleton::parent(bone.id).is_none() && skinned.contains(&bone.skeleton_id) {
+            *visibility = bone_visibility;
+        }
+    }
+}
+
+/// Parent-relative angles of one bone of the current skeleton, in degrees.
+/// Editing them turns the bone, "Save pose" keeps the change.
+fn joint_angles_ui(
+    ui: &mut egui::Ui,
+    yoga_assets: &mut YogaAssets,
+    bones: &mut Query<(Entity, &mut Transform, &Bone)>,
+    names: &Query<&Name>,
+) {
+    let mut current = bones
+        .iter_mut()
+        .filter(|(_entity, _transform, bone)| bone.skeleton_id == CURRENT_SKELETON)
+        .collect::<Vec<_>>();
+    current.sort_by_key(|(_entity, _transform, bone)| bone.id);
+    let name_of = |entity: Entity| {
+        names
+            .get(entity)
+            .map(|name| name.as_str().to_string())
+            .unwrap_or_default()
+    };
+
+    let selected_name = current
+        .iter()
+        .find(|(_entity, _transform, bone)| bone.id == yoga_assets.angle_joint)
+        .map(|(entity, _transform, _bone)| name_of(*entity))
+        .unwrap_or_default();
+    egui::ComboBox::from_label("Bone")
+        .selected_text(selected_name)
+        .show_ui(ui, |ui| {
+            for (entity, _transform, bone) in current.iter() {
+                ui.selectable_value(&mut yoga_assets.angle_joint, bone.id, name_of(*entity));
+            }
+        });
+
+    let Some((_entity, transform, bone)) = current
+        .iter_mut()
+        .find(|(_entity, _transform, bone)| bone.id == yoga_assets.angle_joint)
+    else {
+        return;
+    };
+    // Angles that came with the data read better ("90°" rather than
+    // "89.99°"), so show them for as long as the bone hasn't been turned.
+    let stored = yoga_assets
+        .asanas
+        .asanas
+        .get(yoga_assets.current_idx)
+        .and_then(|asana| yoga_assets.asanas.poses.get(&asana.pose_id))
+        .and_then(|joints| joints.iter().find(|joint| joint.joint_id == bone.id))
+        .and_then(|joint| joint.angles);
+    let mut joint = Joint::from_matrix(bone.id, 0, &transform.compute_matrix());
+    joint.angles = stored.filter(|angles| joint.angles_match(Vec3::from(*angles)));
+    let mut angles = joint.euler_degrees();
+
+    ui.checkbox(&mut yoga_assets.clamp_to_limits, "Clamp edits to joint limits");
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        for (label, value) in [("x", &mut angles.x), ("y", &mut angles.y), ("z", &mut angles.z)] {
+            ui.label(label);
+            changed |= ui
+                .add(
+                    egui::DragValue::new(value)
+                        .speed(0.5)
+                        .range(-180.0..=180.0)
+                        .suffix("°")
+                        .max_decimals(1),
+                )
+                .changed();
+        }
+    });
+    if changed {
+        transform.rotation = skeleton::rotation_from_degrees(angles);
+        if yoga_assets.clamp_to_limits {
+            transform.rotation = limits::range_of_motion(bone.id).clamp(transform.rotation);
+        }
+    }
+    for violation in limits::joint_violations(bone.id, transform.rotation) {
+        ui.colored_label(egui::Color32::LIGHT_RED, violation.to_string());
+    }
+}
+
+/// Keeps a bone turned with the gizmo within its range of motion.
+#[allow(clippy::type_complexity)]
+fn clamp_gizmo_edits(
+    yoga_assets: Res<YogaAssets>,
+    mut bones: Query<(&Bone, &mut Transform), (With<GizmoTarget>, Changed<Transform>)>,
+) {
+    if !yoga_assets.clamp_to_limits {
+        return;
+    }
+    for (bone, mut transform) in bones.iter_mut() {
+        let clamped = limits::range_of_motion(bone.id).clamp(transform.rotation);
+        if clamped != transform.rotation {
+            transform.rotation = clamped;
+        }
+    }
+}
+
+/// Finds the bones passing through each other in every skeleton a bone of
+/// which moved, and in the current one when its body changed.
+fn find_collisions(
+    body_bones: Res<BodyBones>,
+    moved: Query<&Bone, Changed<Transform>>,
+    bones: Query<(&Bone, &Transform)>,
+    mut collisions: ResMut<SkeletonCollisions>,
+) {
+    let mut skeleton_ids = moved.iter().map(|bone| bone.skeleton_id).collect::<BTreeSet<i32>>();
+    if body_bones.is_changed() {
+        // The body of the current skeleton changed shape.
+        skeleton_ids.insert(CURRENT_SKELETON);
+    }
+    for skeleton_id in skeleton_ids {
+        let skeleton_bones = if skeleton_id == CURRENT_SKELETON {
+            &body_bones.0
+        } else {
+            skeleton::bones()
+        };
+        let matrices = bones
+            .iter()
+            .filter(|(bone, _transform)| bone.skeleton_id == skeleton_id)
+            .map(|(bone, transform)| (bone.id, transform.compute_matrix()))
+            .collect::<Vec<(i32, Mat4)>>();
+        let found = kinematics::pose_skeleton(skeleton_bones, Mat4::IDENTITY, &matrices)
+            .map(|posed| collision::collisions(skeleton_bones, &posed))
+            .unwrap_or_default();
+        if collisions.0.get(&skeleton_id) != Some(&found) {
+            collisions.0.insert(skeleton_id, found);
+        }
+    }
+}
+
+/// Colours the bones that are turned past their range of motion, and those
+/// passing through another bone.
+fn highlight_bones(
+    bone_materials: Res<BoneMaterials>,
+    collisions: Res<SkeletonCollisions>,
+    mut bones: Query<(&Bone, Ref<Transform>, &mut MeshMaterial3d<StandardMaterial>)>,
+) {
+    for (bone, transform, mut material) in bones.iter_mut() {
+        if !transform.is_changed() && !collisions.is_changed() {
+            continue;
+        }
+        let colliding = collisions.0.get(&bone.skeleton_id).is_some_and(|found| {
+            found
+                .iter()
+                .any(|collision| collision.joints.0 == bone.id || collision.joints.1 == bone.id)
+        });
+        let handle = if !limits::joint_violations(bone.id, transform.rotation).is_empty() {
+            &bone_materials.out_of_range
+        } else if colliding {
+            &bone_materials.colliding
+        } else {
+            &bone_materials.bone
+        };
+        if material.0 != *handle {
+            material.0 = handle.clone();
+        }
+    }
+}
+
+/// Rests every skeleton on the mat whenever a bone moves, or puts it back
+/// where it was spawned.
+#[allow(clippy::type_complexity)]
+fn ground_skeletons(
+    mut applied: Local<Option<(bool, bool)>>,
+    yoga_assets: Res<YogaAssets>,
+    body_bones: Res<BodyBones>,
+    moved: Query<(), (With<Bone>, Changed<Transform>, Without<Skeleton>)>,
+    bones: Query<(&Bone, &Transform), Without<Skeleton>>,
+    mut skeletons: Query<(&Skeleton, &mut Transform)>,
+) {
+    let settings = (yoga_assets.ground, yoga_assets.centre);
+    if *applied == Some(settings) && moved.is_empty() {
+        return;
+    }
+    *applied = Some(settings);
+    for (skeleton, mut transform) in skeletons.iter_mut() {
+        let placed = if yoga_assets.ground {
+            let body_bones = if skeleton.id == CURRENT_SKELETON {
+                &body_bones.0
+            } else {
+                skeleton::bones()
+            };
+            let matrices = bones
+                .iter()
+                .filter(|(bone, _transform)| bone.skeleton_id == skeleton.id)
+                .map(|(bone, transform)| (bone.id, transform.compute_matrix()))
+                .collect::<Vec<(i32, Mat4)>>();
+            let Ok(posed) = kinematics::pose_skeleton(body_bones, Mat4::IDENTITY, &matrices) else {
+                continue;
+            };
+            let centre = yoga_assets.centre.then_some(skeleton.home.translation);
+            grounding::ground(body_bones, &posed, skeleton.home, centre)
+        } else {
+            skeleton.home
+        };
+        if *transform != placed {
+            *transform = placed;
+        }
+    }
+}
+
+/// Shows the inverse kinematics handles while they're switched on.
+fn show_ik_handles(
+    mut shown: Local<Option<bool>>,
+    yoga_assets: Res<YogaAssets>,
+    mut handles: Query<&mut Visibility, With<IkHandle>>,
+) {
+    if *shown == Some(yoga_assets.ik) {
+        return;
+    }
+    *shown = Some(yoga_assets.ik);
+    for mut visibility in handles.iter_mut() {
+        *visibility = if yoga_assets.ik {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Parent-relative transforms of the current skeleton's bones.
+fn current_locals<'a>(bones: impl Iterator<Item = (&'a Bone, &'a Transform)>) -> HashMap<i32, Transform> {
+    bones
+        .filter(|(bone, _transform)| bone.skeleton_id == CURRENT_SKELETON)
+        .map(|(bone, transform)| (bone.id, *transform))
+        .collect()
+}
+
+/// Where `joint_id` is relative to the skeleton root, which the handles are
+/// children of.
+fn root_space(joint_id: i32, locals: &HashMap<i32, Transform>) -> Transform {
+    skeleton::ancestors(joint_id)
+        .iter()
+        .rev()
+        .fold(Transform::IDENTITY, |above, id| {
+            above.mul_transform(locals.get(id).copied().unwrap_or_default())
+        })
+}
+
+/// The joint the chain hangs from, in root space, and the chain's own
+/// parent-relative transforms.
+fn chain_locals(chain: &ik::Chain, locals: &HashMap<i32, Transform>) -> (Transform, Vec<Transform>) {
+    let parent = skeleton::parent(chain.joints[0])
+        .map(|parent| root_space(parent, locals))
+        .unwrap_or_default();
+    let chain_locals = chain
+        .joints
+        .iter()
+        .map(|joint_id| locals.get(joint_id).copied().unwrap_or_default())
+        .collect();
+    (parent, chain_locals)
+}
+
+/// Solves the chain of a handle being moved with the gizmo: the hand or foot
+/// goes to the target handle and the elbow or knee points at the pole.
+#[allow(clippy::type_complexity)]
+fn solve_ik(
+    yoga_assets: Res<YogaAssets>,
+    moved: Query<&IkHandle, (With<GizmoTarget>, Changed<Transform>)>,
+    handles: Query<(&IkHandle, &Transform), Without<Bone>>,
+    mut bones: Query<(&Bone, &mut Transform), Without<IkHandle>>,
+) {
+    if !yoga_assets.ik {
+        return;
+    }
+    let mut chains = moved.iter().map(|handle| handle.chain).collect::<Vec<usize>>();
+    chains.sort_unstable();
+    chains.dedup();
+    for index in chains {
+        let chain = &ik::CHAINS[index];
+        let handle = |pole: bool| {
+            handles
+                .iter()
+                .find(|(handle, _transform)| handle.chain == index && handle.pole == pole)
+                .map(|(_handle, transform)| *transform)
+        };
+        let Some(target) = handle(false) else {
+            continue;
+        };
+        let locals = current_locals(bones.iter());
+        let (parent, mut chain_locals) = chain_locals(chain, &locals);
+        let goal = ik::Goal {
+            position: target.translation,
+            rotation: Some(target.rotation),
+            pole: handle(true).map(|pole| pole.translation),
+        };
+        ik::solve(chain, parent, &mut chain_locals, &goal);
+        for (bone, mut transform) in bones.iter_mut() {
+            if bone.skeleton_id != CURRENT_SKELETON {
+                continue;
+            }
+            if let Some(position) = chain.joints.iter().position(|joint_id| *joint_id == bone.id) {
+                if transform.rotation != chain_locals[position].rotation {
+                    transform.rotation = chain_locals[position].rotation;
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the handles that aren't being moved on the hand or foot, and the
+/// elbow or knee, they belong to.
+#[allow(clippy::type_complexity)]
+fn place_ik_handles(
+    yoga_assets: Res<YogaAssets>,
+    bones: Query<(&Bone, &Transform), Without<IkHandle>>,
+    mut handles: Query<(&IkHandle, &mut Transform), Without<GizmoTarget>>,
+) {
+    if !yoga_assets.ik {
+        return;
+    }
+    let locals = current_locals(bones.iter());
+    for (handle, mut transform) in handles.iter_mut() {
+        let chain = &ik::CHAINS[handle.chain];
+        let placed = if handle.pole {
+            let (parent, chain_locals) = chain_locals(chain, &locals);
+            let Some(pole) = chain.pole(parent, &chain_locals) else {
+                continue;
+            };
+            Transform::from_translation(pole)
+        } else {
+            root_space(chain.joints[chain.joints.len() - 1], &locals)
+        };
+        if *transform != placed {
+            *transform = placed;
+        }
+    }
+}
+
 fn initial_pose(
     mut yoga_assets: ResMut<YogaAssets>,
     bones: Query<(Entity, &mut Transform, &Bone)>,
     asana_text: Query<&mut Text, With<AsanaName>>,
 ) {
     yoga_assets.current_idx = 127;
-    set_pose(yoga_assets, bones, asana_text);
+    set_pose(yoga_assets, bones, asana_text, true);
 }
 
+/// Poses every skeleton, the current one with the selected asana. With
+/// `animate` the bones move there over `YogaAssets::transition_seconds`,
+/// otherwise they jump.
 fn set_pose(
     yoga_assets: ResMut<YogaAssets>,
+    bones: Query<(Entity, &mut Transform, &Bone)>,
+    asana_text: Query<&mut Text, With<AsanaName>>,
+    animate: bool,
+) {
+    let seconds = if animate { yoga_assets.transition_seconds } else { 0.0 };
+    set_pose_over(yoga_assets, bones, asana_text, seconds);
+}
+
+/// `set_pose` moving the bones from wherever they are over `seconds`.
+fn set_pose_over(
+    mut yoga_assets: ResMut<YogaAssets>,
     mut bones: Query<(Entity, &mut Transform, &Bone)>,
     mut asana_text: Query<&mut Text, With<AsanaName>>,
+    seconds: f32,
 ) {
-    let name = yoga_assets.asanas.asanas[yoga_assets.current_idx]
-        .sanskrit
-        .clone();
-
     let mut change_me = asana_text.single_mut().unwrap();
-    *change_me = Text::new(&name);
+    let Some(asana) = yoga_assets.asanas.asanas.get(yoga_assets.current_idx) else {
+        *change_me = Text::new("No poses loaded");
+        return;
+    };
+    let display_name = asana.unicode.clone().unwrap_or_else(|| asana.sanskrit.clone());
+
+    let animate = seconds > 0.0;
+    let mut moves = Vec::new();
+    let mut errors = Vec::new();
+    let posed = [(CURRENT_SKELETON, Ok(asana))]
+        .into_iter()
+        .chain(OTHER_SKELETONS.map(|(skeleton_id, asana_id)| {
+            (skeleton_id, pose::find_asana(&yoga_assets.asanas, asana_id))
+        }))
+        .map(|(skeleton_id, asana)| {
+            let joint_ids = bones
+                .iter()
+                .filter(|(_entity, _transform, bone)| bone.skeleton_id == skeleton_id)
+                .map(|(_entity, _transform, bone)| bone.id)
+                .collect::<Vec<i32>>();
+            let matrices = asana.and_then(|asana| pose::pose_matrices(&yoga_assets.asanas, asana, &joint_ids));
+            (skeleton_id, matrices)
+        })
+        .collect::<Vec<_>>();
+    for (skeleton_id, matrices) in posed {
+        match matrices {
+            Ok(matrices) => {
+                let matrices = if skeleton_id == CURRENT_SKELETON && yoga_assets.mirrored() {
+                    mirror::mirror_matrices(&matrices)
+                } else {
+                    matrices
+                };
+                for (entity, mut transform, bone) in bones.iter_mut() {
+                    if bone.skeleton_id != skeleton_id {
+                        continue;
+                    }
+                    if let Some((_joint_id, mat)) =
+                        matrices.iter().find(|(joint_id, _mat)| *joint_id == bone.id)
+                    {
+                        let target = if skeleton_id == CURRENT_SKELETON {
+                            Transform::from_matrix(yoga_assets.body.retarget_matrix(bone.id, *mat))
+                        } else {
+                            Transform::from_matrix(*mat)
+                        };
+                        if animate {
+                            moves.push((entity, *transform, target));
+                        } else {
+                            *transform = target;
+                        }
+                    }
+                }
+                if skeleton_id == CURRENT_SKELETON {
+                    *change_me = Text::new(display_name.clone());
+                }
+            }
+            Err(e) => {
+                error!("skeleton {skeleton_id} keeps its previous pose: {e}");
+                errors.push(e.to_string());
+            }
+        }
+    }
+    yoga_assets.pose_error = (!errors.is_empty())
+        .then(|| format!("Kept the previous pose.\n{}", errors.join("\n")));
+    yoga_assets.transition = animate.then(|| {
+        Transition::new(moves, seconds, yoga_assets.easing)
+    });
+}
+
+/// Steps the current skeleton through the sequence being played, moving
+/// into each asana over the step's transition time.
+fn play_sequence(
+    time: Res<Time>,
+    mut yoga_assets: ResMut<YogaAssets>,
+    bones: Query<(Entity, &mut Transform, &Bone)>,
+    asana_text: Query<&mut Text, With<AsanaName>>,
+    mut sequence_player: ResMut<SequencePlayer>,
+) {
+    let skipped_to = sequence_player.skipped_to.take();
+    let player = &mut sequence_player.player;
+    let Some(step) = player
+        .advance(time.delta_secs())
+        .or(skipped_to)
+        .and_then(|step| player.sequence().steps.get(step))
+    else {
+        return;
+    };
+    let Some(asana_idx) = yoga_assets
+        .asanas
+        .asanas
+        .iter()
+        .position(|asana| asana.asana_id == step.asana_id)
+    else {
+        yoga_assets.pose_error = Some(format!(
+            "{} has no asana {} to play.",
+            player.sequence().name,
+            step.asana_id
+        ));
+        player.pause();
+        return;
+    };
+    yoga_assets.current_idx = asana_idx;
+    yoga_assets.sequence_side = step.other_side;
+    let seconds = player.transition();
+    set_pose_over(yoga_assets, bones, asana_text, seconds);
+}
 
-    let tadasana = load_pose("Tadasana", &yoga_assets);
-    let pose_joints = load_pose(&name, &yoga_assets);
-    let virasana = load_pose("Virasana", &yoga_assets);
-    let matsyendrasana = load_pose("Ardha Matsyendrasana", &yoga_assets);
+/// Poses the current skeleton from the timeline while it plays or is
+/// scrubbed.
+fn play_timeline(
+    time: Res<Time>,
+    mut yoga_assets: ResMut<YogaAssets>,
+    mut editor: ResMut<TimelineEditor>,
+    mut bones: Query<(&Bone, &mut Transform)>,
+) {
+    if editor.playing {
+        let duration = editor.timeline.duration();
+        editor.time += time.delta_secs();
+        if editor.time >= duration {
+            editor.time = duration;
+            editor.playing = false;
+        }
+    } else if !editor.scrubbed {
+        return;
+    }
+    editor.scrubbed = false;
+    let Some(matrices) = editor.timeline.sample(editor.time) else {
+        return;
+    };
+    if yoga_assets.transition.is_some() {
+        yoga_assets.transition = None;
+    }
+    for (bone, mut transform) in bones.iter_mut() {
+        if bone.skeleton_id != CURRENT_SKELETON {
+            continue;
+        }
+        if let Some((_joint_id, mat)) = matrices.iter().find(|(joint_id, _mat)| *joint_id == bone.id) {
+            *transform = Transform::from_matrix(yoga_assets.body.retarget_matrix(bone.id, *mat));
+        }
+    }
+}
 
-    for (_entity, mut transform, bone) in bones.iter_mut() {
-        if bone.skeleton_id == 0 {
-            let pose_mat = tadasana.iter().find(|j| j.joint_id == bone.id).unwrap();
-            *transform = Transform::from_matrix(pose_mat.mat);
+/// Grows and shrinks the breath ring with the breath of the sequence being
+/// played, and names the breath beside it.
+#[allow(clippy::type_complexity)]
+fn show_breath(
+    sequence_player: Res<SequencePlayer>,
+    mut ring: Query<(&mut Node, &mut Visibility), With<BreathRing>>,
+    mut text: Query<(&mut Text, &mut Visibility), (With<BreathText>, Without<BreathRing>)>,
+) {
+    let player = &sequence_player.player;
+    let breath = player.breath().filter(|_| player.is_playing());
+    let visibility = if breath.is_some() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    let (phase, progress) = breath.unwrap_or((Phase::Exhale, 1.0));
+    let filled = EaseFunction::SineInOut.sample_clamped(progress);
+    let (label, filled) = match phase {
+        Phase::Inhale => ("Inhale", filled),
+        Phase::Exhale => ("Exhale", 1.0 - filled),
+    };
+    let size = Val::Px(BREATH_RING.0 + (BREATH_RING.1 - BREATH_RING.0) * filled);
+    for (mut node, mut shown) in ring.iter_mut() {
+        node.width = size;
+        node.height = size;
+        shown.set_if_neq(visibility);
+    }
+    for (mut text, mut shown) in text.iter_mut() {
+        if text.0 != label {
+            text.0 = label.to_string();
         }
-        if bone.skeleton_id == 1 {
-            let pose_mat = pose_joints.iter().find(|j| j.joint_id == bone.id).unwrap();
-            *transform = Transform::from_matrix(pose_mat.mat);
+        shown.set_if_neq(visibility);
+    }
+}
+
+/// Moves the bones along the transition `set_pose` started.
+fn animate_transition(
+    time: Res<Time>,
+    mut yoga_assets: ResMut<YogaAssets>,
+    mut bones: Query<&mut Transform, With<Bone>>,
+) {
+    // Moving along isn't a change of settings.
+    let Some(transition) = yoga_assets.bypass_change_detection().transition.as_mut() else {
+        return;
+    };
+    for (entity, placed) in transition.advance(time.delta_secs()) {
+        if let Ok(mut transform) = bones.get_mut(entity) {
+            *transform = placed;
         }
-        if bone.skeleton_id == 2 {
-            let pose_mat = virasana.iter().find(|j| j.joint_id == bone.id).unwrap();
-            *transform = Transform::from_matrix(pose_mat.mat);
+    }
+    if transition.is_finished() {
+        yoga_assets.transition = None;
+    }
+}
+
+/// Writes the bones of the current skeleton back into the pose database
+/// the app loaded. A tree is written out again. An out_db is re-encoded,
+/// after the SQLite database it's exported from is updated if it sits next
+/// to it, so the edit also reaches the wasm build.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_pose(
+    yoga_assets: &mut YogaAssets,
+    source: &PoseDatabaseSource,
+    bones: &Query<(Entity, &mut Transform, &Bone)>,
+) {
+    let Some(asana) = yoga_assets.asanas.asanas.get(yoga_assets.current_idx) else {
+        return;
+    };
+    let pose_id = asana.pose_id;
+    let name = asana.sanskrit.clone();
+
+    let mut joints = bones
+        .iter()
+        .filter(|(_entity, _transform, bone)| bone.skeleton_id == CURRENT_SKELETON)
+        .map(|(_entity, transform, bone)| {
+            let joint = Joint::from_matrix(bone.id, pose_id, &transform.compute_matrix());
+            yoga_assets.body.to_default_body(&joint)
+        })
+        .collect::<Vec<Joint>>();
+    joints.sort_by_key(|joint| joint.joint_id);
+    if yoga_assets.mirrored() {
+        joints = mirror::mirror_pose(&joints);
+    }
+
+    let previous = yoga_assets.asanas.poses.insert(pose_id, joints);
+    let data = &yoga_assets.asanas;
+    let saved = match source {
+        PoseDatabaseSource::Asset { file, .. } => save_out_db(data, &data.poses[&pose_id], file),
+        PoseDatabaseSource::Tree(dir) => text::write_tree(data, dir)
+            .map(|()| dir.display().to_string())
+            .map_err(|e| e.to_string()),
+    };
+    yoga_assets.save_status = Some(match saved {
+        Ok(written) => {
+            info!("saved {name} to {written}");
+            Ok(format!("Saved {name} to {written}"))
         }
-        if bone.skeleton_id == 3 {
-            let pose_mat = matsyendrasana.iter().find(|j| j.joint_id == bone.id).unwrap();
-            *transform = Transform::from_matrix(pose_mat.mat);
+        Err(e) => {
+            error!("saving {name} failed: {e}");
+            match previous {
+                Some(joints) => yoga_assets.asanas.poses.insert(pose_id, joints),
+                None => yoga_assets.asanas.poses.remove(&pose_id),
+            };
+            Err(format!("{name} wasn't saved.\n{e}"))
         }
+    });
+}
+
+/// Updates `joints` in the SQLite database next to `out_db`, if there is
+/// one, then encodes `data` into `out_db`. Returns what was written.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_out_db(data: &AsanaData, joints: &[Joint], out_db: &std::path::Path) -> Result<String, String> {
+    let sqlite = out_db.with_file_name(std::path::Path::new(db::SQLITE_PATH).file_name().unwrap());
+    let mut written = Vec::new();
+    if sqlite.is_file() {
+        db::update_pose(&sqlite, joints).map_err(|e| format!("{}: {e}", sqlite.display()))?;
+        written.push(sqlite.display().to_string());
     }
+    db::write_encoded(data, out_db).map_err(|e| format!("{}: {e}", out_db.display()))?;
+    written.push(out_db.display().to_string());
+    Ok(written.join(" and "))
 }
 
-fn load_resources(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn load_resources(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    database_source: Res<PoseDatabaseSource>,
+) {
     //serialize_db();
-    let asana_data: AsanaData = deserialize_db();
+    #[allow(unused_mut)]
+    let (mut asana_data, mut database_error) = match deserialize_db() {
+        Ok(data) => (data, None),
+        Err(e) => {
+            error!("embedded out_db couldn't be decoded: {e}");
+            (
+                AsanaData::default(),
+                Some(format!("Built-in poses are unreadable: {e}")),
+            )
+        }
+    };
+    match database_source.as_ref() {
+        PoseDatabaseSource::Asset { path, .. } => commands.insert_resource(PoseDatabaseHandle(
+            asset_server.load::<PoseDatabase>(path.clone()),
+        )),
+        #[cfg(not(target_arch = "wasm32"))]
+        PoseDatabaseSource::Tree(dir) => match text::read_tree(dir) {
+            Ok(data) => {
+                info!("read {} asanas from {}", data.asanas.len(), dir.display());
+                asana_data = data;
+                database_error = None;
+            }
+            Err(e) => {
+                error!("{} couldn't be read: {e}", dir.display());
+                database_error = Some(format!(
+                    "{} couldn't be read, showing built-in poses.\n{e}",
+                    dir.display()
+                ));
+            }
+        },
+    }
+    check_database(&asana_data);
     commands.insert_resource(YogaAssets {
         font: asset_server.load("fonts/Roboto-Regular.ttf"),
         font_color: Color::srgb_u8(207, 207, 207),
@@ -246,6 +1586,23 @@ fn load_resources(mut commands: Commands, asset_server: Res<AssetServer>) {
         current_idx: 0,
         asana_name_entry: String::new(),
         check_sanskrit: false,
+        selected_categories: Vec::new(),
+        database_error,
+        pose_error: None,
+        save_status: None,
+        angle_joint: 1,
+        body: BodyProportions::default(),
+        smooth_body: false,
+        clamp_to_limits: false,
+        ik: false,
+        other_side: false,
+        sequence_side: false,
+        ground: false,
+        centre: false,
+        balance: false,
+        transition_seconds: 0.8,
+        easing: EaseFunction::SmoothStep,
+        transition: None,
     });
 }
 
@@ -255,40 +1612,217 @@ fn keyboard_input_system(
     bones: Query<(Entity, &mut Transform, &Bone)>,
     asana_text: Query<&mut Text, With<AsanaName>>,
 ) {
+    if yoga_assets.asanas.asanas.is_empty() {
+        return;
+    }
     if keyboard_input.just_pressed(KeyCode::ArrowUp) {
         yoga_assets.current_idx = (yoga_assets.current_idx + 1) % yoga_assets.asanas.asanas.len();
-        set_pose(yoga_assets, bones, asana_text);
+        yoga_assets.sequence_side = false;
+        set_pose(yoga_assets, bones, asana_text, true);
     } else if keyboard_input.just_pressed(KeyCode::ArrowDown) {
         let length = yoga_assets.asanas.asanas.len();
         yoga_assets.current_idx = (yoga_assets.current_idx + length - 1) % length;
-        set_pose(yoga_assets, bones, asana_text);
+        yoga_assets.sequence_side = false;
+        set_pose(yoga_assets, bones, asana_text, true);
+    }
+}
+
+/// Debug builds log what `validate` finds in every pose database they load.
+fn check_database(data: &AsanaData) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let report = validate::validate(data);
+    for issue in report.issues.iter() {
+        debug!("{issue}");
+    }
+    if !report.is_clean() {
+        warn!(
+            "{} problems in the pose database, `database validate` lists them",
+            report.issues.len()
+        );
     }
 }
 
-fn deserialize_db() -> AsanaData {
+/// The copy of out_db built into the binary, used until the runtime one
+/// has loaded or when it can't be.
+fn deserialize_db() -> Result<AsanaData, format::FormatError> {
     // asanas: Vec<AsanaDB>,
     // poses: HashMap<i32, Vec<Joint>>,
     let db = include_bytes!("../out_db");
-    bincode::decode_from_slice(db, bincode::config::legacy())
-        .unwrap()
-        .0
+    format::decode(db)
 }
 
-fn load_pose(sanskrit: &str, yoga: &YogaAssets) -> Vec<JointMatrix> {
-    let asana = yoga
-        .asanas
-        .asanas
-        .iter()
-        .find(|asana| asana.sanskrit == sanskrit)
-        .unwrap();
-    let joints = yoga.asanas.poses.get(&asana.pose_id).unwrap();
-    joints
+fn swap_in_database(
+    mut loaded: EventReader<AssetEvent<PoseDatabase>>,
+    mut failed: EventReader<AssetLoadFailedEvent<PoseDatabase>>,
+    handle: Option<Res<PoseDatabaseHandle>>,
+    mut databases: ResMut<Assets<PoseDatabase>>,
+    mut yoga_assets: ResMut<YogaAssets>,
+    bones: Query<(Entity, &mut Transform, &Bone)>,
+    asana_text: Query<&mut Text, With<AsanaName>>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    for failure in failed.read() {
+        if failure.id == handle.0.id() {
+            error!("{} couldn't be loaded: {}", failure.path, failure.error);
+            let fallback = if yoga_assets.asanas.asanas.is_empty() {
+                "no poses available"
+            } else {
+                "showing built-in poses"
+            };
+            yoga_assets.database_error = Some(format!(
+                "{} couldn't be loaded, {fallback}.\n{}",
+                failure.path, failure.error
+            ));
+        }
+    }
+    let mut swapped = false;
+    for event in loaded.read() {
+        if event.is_loaded_with_dependencies(&handle.0) {
+            if let Some(database) = databases.remove(&handle.0) {
+                info!("loaded {} asanas at runtime", database.0.asanas.len());
+                yoga_assets.replace_asanas(database.0);
+                yoga_assets.database_error = None;
+                swapped = true;
+            }
+        }
+    }
+    if swapped {
+        set_pose(yoga_assets, bones, asana_text, true);
+    }
+}
+
+/// Draws the dristi of the current asana as a ray from the eyes of the
+/// current skeleton.
+fn draw_gaze(
+    yoga_assets: Res<YogaAssets>,
+    body_bones: Res<BodyBones>,
+    bones: Query<(&GlobalTransform, &Bone, &Name)>,
+    mut gizmos: Gizmos,
+) {
+    let Some(asana) = yoga_assets.asanas.asanas.get(yoga_assets.current_idx) else {
+        return;
+    };
+    let Some(dristi) = asana.dristi else {
+        return;
+    };
+    let bone_global = |name: &str| {
+        bones
+            .iter()
+            .find(|(_global, bone, bone_name)| {
+                bone.skeleton_id == CURRENT_SKELETON && bone_name.as_str() == name
+            })
+            .map(|(global, _bone, _name)| *global)
+    };
+    let Some(head) = bone_global("Head") else {
+        return;
+    };
+    let find_bone = |name: &str| body_bones.iter().find(|bone| bone.cube.name == name);
+    let Some(head_bone) = find_bone("Head") else {
+        return;
+    };
+    let head_cube = &head_bone.cube;
+    // the head hangs down its joint's -y, the face looks down +z
+    let eye = head.transform_point(head_cube.transform.translation + Vec3::Z * 6.0);
+    let forward = head.affine().transform_vector3(Vec3::Z).normalize();
+    let up = head.affine().transform_vector3(Vec3::NEG_Y).normalize();
+    let left = head.affine().transform_vector3(Vec3::NEG_X).normalize();
+    let reach = 40.0 * head.scale().y;
+
+    let tips = dristi
+        .target_bones()
         .iter()
-        .map(|joint| JointMatrix {
-            mat: joint.matrix(),
-            joint_id: joint.joint_id,
+        .filter_map(|name| {
+            let cube = &find_bone(name)?.cube;
+            let tip = if *name == "Hips" {
+                cube.transform.translation
+            } else {
+                cube.transform.translation - Vec3::Y * cube.y / 2.0
+            };
+            bone_global(name).map(|global| global.transform_point(tip))
         })
-        .collect::<Vec<JointMatrix>>()
+        .collect::<Vec<Vec3>>();
+
+    let target = match dristi {
+        Dristi::Unspecified => return,
+        Dristi::Nose => eye + (forward - up * 0.5).normalize() * reach,
+        Dristi::ThirdEye => eye + (forward + up * 0.5).normalize() * reach,
+        Dristi::FarLeft => eye + left * reach,
+        Dristi::FarRight => eye - left * reach,
+        Dristi::Up => eye + Vec3::Y * reach,
+        // look at the hand or foot that is higher up
+        Dristi::Hand | Dristi::Toes | Dristi::Navel => {
+            let Some(tip) = tips.into_iter().reduce(|a, b| if b.y > a.y { b } else { a }) else {
+                return;
+            };
+            tip
+        }
+        Dristi::Thumbs => {
+            if tips.is_empty() {
+                return;
+            }
+            tips.iter().sum::<Vec3>() / tips.len() as f32
+        }
+    };
+    gizmos.arrow(eye, target, Color::srgb_u8(255, 214, 10));
+}
+
+/// Draws the centre of mass of the current skeleton with a plumb line down
+/// to its lowest point, and the polygon it's supported by: green while the centre of
+/// mass is over it, red when the pose would tip.
+fn draw_balance(
+    yoga_assets: Res<YogaAssets>,
+    body_bones: Res<BodyBones>,
+    bones: Query<(&Bone, &Transform)>,
+    skeletons: Query<(&Skeleton, &GlobalTransform)>,
+    mut current_balance: ResMut<CurrentBalance>,
+    mut gizmos: Gizmos,
+) {
+    if !yoga_assets.balance {
+        current_balance.0 = None;
+        return;
+    }
+    let Some((_skeleton, root)) = skeletons
+        .iter()
+        .find(|(skeleton, _global)| skeleton.id == CURRENT_SKELETON)
+    else {
+        return;
+    };
+    let matrices = bones
+        .iter()
+        .filter(|(bone, _transform)| bone.skeleton_id == CURRENT_SKELETON)
+        .map(|(bone, transform)| (bone.id, transform.compute_matrix()))
+        .collect::<Vec<(i32, Mat4)>>();
+    let Ok(posed) = kinematics::pose_skeleton(&body_bones, Mat4::IDENTITY, &matrices) else {
+        current_balance.0 = None;
+        return;
+    };
+    let balance = balance::balance(&body_bones, &posed, root.compute_transform());
+
+    let colour = match balance.margin {
+        Some(margin) if margin >= 0.0 => Color::srgb_u8(80, 220, 100),
+        _ => Color::srgb_u8(230, 60, 50),
+    };
+    let com = balance.centre_of_mass;
+    let plumb = Vec3::new(com.x, balance.floor, com.z);
+    gizmos.sphere(Isometry3d::from_translation(com), 1.5, colour);
+    gizmos.line(com, plumb, colour);
+    gizmos.circle(
+        Isometry3d::new(plumb + Vec3::Y * 0.1, Quat::from_rotation_x(FRAC_PI_2)),
+        0.75,
+        colour,
+    );
+    if let Some(first) = balance.support.first() {
+        let lift = Vec3::Y * 0.1;
+        gizmos.linestrip(
+            balance.support.iter().chain([first]).map(|corner| *corner + lift),
+            colour,
+        );
+    }
+    current_balance.0 = Some(balance);
 }
 
 fn default_viewpoint() -> (Transform, Vec3) {
@@ -342,8 +1876,8 @@ fn spawn_mat(
 
     commands
         .spawn((
-            Mesh3d(meshes.add(Cuboid::new(57.35 * 2.0, 0.5 * 2.0, 21.7 * 2.0))),
-            Transform::from_xyz(0.0, -109.5, 0.0),
+            Mesh3d(meshes.add(Cuboid::from_size(grounding::MAT_SIZE))),
+            Transform::from_translation(grounding::MAT_CENTRE),
             MeshMaterial3d(material_handle),
         ))
         .observe(bone_click);
@@ -383,6 +1917,7 @@ fn spawn_mat(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_bone(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
@@ -439,61 +1974,193 @@ fn spawn_main_skeleton(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    spawn_skeleton(commands.reborrow(), &mut meshes, &mut materials, 0, Transform::IDENTITY);
+    let bone = materials.add(StandardMaterial {
+        base_color: Color::srgba_u8(166, 116, 51, 255),
+        reflectance: 0.2,
+        perceptual_roughness: 0.95,
+        ..Default::default()
+    });
+    let out_of_range = materials.add(StandardMaterial {
+        base_color: Color::srgb_u8(200, 40, 30),
+        reflectance: 0.2,
+        perceptual_roughness: 0.95,
+        ..Default::default()
+    });
+    let colliding = materials.add(StandardMaterial {
+        base_color: Color::srgb_u8(130, 60, 190),
+        reflectance: 0.2,
+        perceptual_roughness: 0.95,
+        ..Default::default()
+    });
+    commands.insert_resource(BoneMaterials {
+        bone: bone.clone(),
+        out_of_range,
+        colliding,
+    });
+
+    spawn_skeleton(commands.reborrow(), &mut meshes, &mut materials, bone.clone(), 0, Transform::IDENTITY);
     let trans = Transform::from_translation(Vec3::new(50.0, 0.0, 0.0)).with_scale(Vec3::splat(0.5));
-    spawn_skeleton(commands.reborrow(), &mut meshes, &mut materials, 1, trans);
+    spawn_skeleton(commands.reborrow(), &mut meshes, &mut materials, bone.clone(), 1, trans);
     let trans = Transform::from_translation(Vec3::new(-50.0, 0.0, 0.0)).with_scale(Vec3::splat(0.5));
-    spawn_skeleton(commands.reborrow(), &mut meshes, &mut materials, 2, trans);
+    spawn_skeleton(commands.reborrow(), &mut meshes, &mut materials, bone.clone(), 2, trans);
     let trans = Transform::from_translation(Vec3::new(100.0, 0.0, 0.0)).with_scale(Vec3::splat(0.5));
-    spawn_skeleton(commands, &mut meshes, &mut materials, 3, trans);
+    spawn_skeleton(commands, &mut meshes, &mut materials, bone, 3, trans);
+}
+
+/// The smooth body for `body`, bound in Tadasana. `None` when Tadasana
+/// can't be posed.
+fn make_body(data: &AsanaData, body: &BodyProportions) -> Option<BodyMesh> {
+    let bones = body.bones();
+    let joint_ids = bones.iter().map(|bone| bone.joint_id).collect::<Vec<i32>>();
+    let bind_pose = match pose::find_asana(data, pose::TADASANA)
+        .and_then(|reference| pose::pose_matrices(data, reference, &joint_ids))
+    {
+        Ok(matrices) => matrices
+            .into_iter()
+            .map(|(joint_id, mat)| (joint_id, body.retarget_matrix(joint_id, mat)))
+            .collect::<Vec<(i32, Mat4)>>(),
+        Err(e) => {
+            error!("no smooth body without a bind pose: {e}");
+            return None;
+        }
+    };
+    body_mesh::make_body_mesh(&bones, &bind_pose)
+        .inspect_err(|e| error!("no smooth body without a bind pose: {e}"))
+        .ok()
+}
+
+/// Gives every skeleton a smooth body, hidden until it's switched on.
+fn spawn_body_skins(
+    mut commands: Commands,
+    yoga_assets: Res<YogaAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut inverse_bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+    skeletons: Query<(Entity, &Skeleton)>,
+    bones: Query<(Entity, &Bone)>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgb_u8(196, 150, 120),
+        perceptual_roughness: 0.8,
+        ..Default::default()
+    });
+    for (empty, skeleton) in skeletons.iter() {
+        let body = if skeleton.id == CURRENT_SKELETON {
+            yoga_assets.body
+        } else {
+            BodyProportions::default()
+        };
+        let Some(body_mesh) = make_body(&yoga_assets.asanas, &body) else {
+            continue;
+        };
+        let joints = body_mesh
+            .joint_ids
+            .iter()
+            .filter_map(|joint_id| {
+                bones
+                    .iter()
+                    .find(|(_entity, bone)| bone.skeleton_id == skeleton.id && bone.id == *joint_id)
+                    .map(|(entity, _bone)| entity)
+            })
+            .collect::<Vec<Entity>>();
+        let skin = commands
+            .spawn((
+                Mesh3d(meshes.add(body_mesh.mesh)),
+                MeshMaterial3d(material.clone()),
+                SkinnedMesh {
+                    inverse_bindposes: inverse_bindposes.add(body_mesh.inverse_bindposes),
+                    joints,
+                },
+                // The bounds of the bind pose don't follow the bones.
+                NoFrustumCulling,
+                Transform::IDENTITY,
+                Visibility::Hidden,
+                Name::from("body"),
+                BodySkin {
+                    skeleton_id: skeleton.id,
+                },
+            ))
+            .id();
+        commands.entity(empty).add_child(skin);
+    }
+}
+
+/// A target and a pole handle for every limb of the current skeleton, hidden
+/// until inverse kinematics is switched on.
+fn spawn_ik_handles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    skeletons: Query<(Entity, &Skeleton)>,
+) {
+    let Some((empty, _skeleton)) = skeletons
+        .iter()
+        .find(|(_entity, skeleton)| skeleton.id == CURRENT_SKELETON)
+    else {
+        return;
+    };
+    let target_material = materials.add(StandardMaterial {
+        base_color: Color::srgb_u8(60, 170, 230),
+        unlit: true,
+        ..Default::default()
+    });
+    let pole_material = materials.add(StandardMaterial {
+        base_color: Color::srgb_u8(230, 200, 60),
+        unlit: true,
+        ..Default::default()
+    });
+    let target_mesh = meshes.add(Sphere::new(2.5));
+    let pole_mesh = meshes.add(Sphere::new(1.5));
+    for (index, chain) in ik::CHAINS.iter().enumerate() {
+        for pole in [false, true] {
+            let (mesh, material, name) = if pole {
+                (&pole_mesh, &pole_material, format!("{} pole", chain.name))
+            } else {
+                (&target_mesh, &target_material, format!("{} target", chain.name))
+            };
+            let handle = commands
+                .spawn((
+                    Mesh3d(mesh.clone()),
+                    MeshMaterial3d(material.clone()),
+                    Transform::IDENTITY,
+                    Visibility::Hidden,
+                    NotShadowCaster,
+                    PickSelection { is_selected: false },
+                    OutlineVolume {
+                        visible: false,
+                        colour: Color::WHITE,
+                        width: 2.0,
+                    },
+                    OutlineStencil::default(),
+                    OutlineMode::default(),
+                    ComputedOutline::default(),
+                    Name::from(name),
+                    IkHandle { chain: index, pole },
+                ))
+                .id();
+            commands.entity(empty).add_child(handle);
+        }
+    }
 }
 
 fn spawn_skeleton(
     mut commands: Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
+    material_handle: Handle<StandardMaterial>,
     skeleton_id: i32,
     hips_transform: Transform,
 ) {
     let make_bone_axis = false;
-    let material = StandardMaterial {
-        base_color: Color::srgba_u8(166, 116, 51, 255),
-        reflectance: 0.2,
-        perceptual_roughness: 0.95,
-        ..Default::default()
-    };
-    let material_handle = materials.add(material);
 
-    let skeleton_parts = skeleton::skelly();
-    let mut bone_id = 1;
     let axis_visible = Visibility::Visible;
 
-    let name = "Hips".to_string();
-    let mut bone = skeleton_parts.get(&name).unwrap();
-    let mesh = make_bone_mesh(bone);
-    let hips = commands
-        .spawn((
-            Mesh3d(meshes.add(mesh)),
-            MeshMaterial3d(material_handle.clone()),
-            Transform::IDENTITY,
-            PickSelection { is_selected: false },
-            OutlineVolume {
-                visible: false,
-                colour: Color::WHITE,
-                width: 2.0,
-            },
-            OutlineStencil::default(),
-            OutlineMode::default(),
-            ComputedOutline::default(),
-            Name::from(name),
-            Bone { id: bone_id, skeleton_id },
-        ))
-        .observe(bone_click)
-        .id();
-
     let empty: Entity = commands
         .spawn((
-            Skeleton { id: skeleton_id },
+            Skeleton {
+                id: skeleton_id,
+                home: hips_transform,
+            },
             hips_transform,
             Visibility::Visible,
             InheritedVisibility::default(),
@@ -501,284 +2168,49 @@ fn spawn_skeleton(
         ))
         .id();
 
-    commands.entity(empty).add_child(hips);
-
-    //commands.entity(hips).add_child(hips);
-
-    if make_bone_axis {
-        let axis = spawn_entity_axis(&mut commands, meshes, materials, axis_visible);
-        commands.entity(hips).add_child(axis);
-    }
-    bone_id += 1;
-
-    let name = "Left Femur".to_string();
-    bone = skeleton_parts.get(&name).unwrap();
-    let mut prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        hips,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Left Calf".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Left Foot".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    _ = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Right Femur".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        hips,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Right Calf".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Right Foot".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    _ = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Lumbar".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = hips;
-    let _l_spine_length = 9.0;
-
-    for _i in (1..=5).rev() {
-        prev_entity = spawn_bone(
-            &mut commands,
-            meshes,
-            material_handle.clone(),
-            materials,
-            bone,
-            bone_id,
-            prev_entity,
-            skeleton_id,
-        );
-        bone_id += 1;
-    }
-
-    let name = "Thoracic".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    let _t_spine_length = 19.0;
-    for _i in (1..=12).rev() {
-        prev_entity = spawn_bone(
+    let mut entities = HashMap::new();
+    for bone in skeleton::bones() {
+        let Some(parent) = bone.parent else {
+            let mesh = make_bone_mesh(&bone.cube);
+            let hips = commands
+                .spawn((
+                    Mesh3d(meshes.add(mesh)),
+                    MeshMaterial3d(material_handle.clone()),
+                    Transform::IDENTITY,
+                    PickSelection { is_selected: false },
+                    OutlineVolume {
+                        visible: false,
+                        colour: Color::WHITE,
+                        width: 2.0,
+                    },
+                    OutlineStencil::default(),
+                    OutlineMode::default(),
+                    ComputedOutline::default(),
+                    Name::from(bone.cube.name.clone()),
+                    Bone { id: bone.joint_id, skeleton_id },
+                ))
+                .observe(bone_click)
+                .id();
+            commands.entity(empty).add_child(hips);
+            if make_bone_axis {
+                let axis = spawn_entity_axis(&mut commands, meshes, materials, axis_visible);
+                commands.entity(hips).add_child(axis);
+            }
+            entities.insert(bone.joint_id, hips);
+            continue;
+        };
+        let entity = spawn_bone(
             &mut commands,
             meshes,
             material_handle.clone(),
             materials,
-            bone,
-            bone_id,
-            prev_entity,
+            &bone.cube,
+            bone.joint_id,
+            entities[&parent],
             skeleton_id,
         );
-        bone_id += 1;
+        entities.insert(bone.joint_id, entity);
     }
-
-    let name = "Cervical".to_string();
-    let mut bone = skeleton_parts.get(&name).unwrap().clone();
-    let _c_spine_length = 8.0;
-    let mut c7 = prev_entity;
-    for i in (1..=7).rev() {
-        bone.name = format!("{name} {i}");
-        prev_entity = spawn_bone(
-            &mut commands,
-            meshes,
-            material_handle.clone(),
-            materials,
-            &bone,
-            bone_id,
-            prev_entity,
-            skeleton_id,
-        );
-        if i == 7 {
-            c7 = prev_entity;
-        }
-        bone_id += 1;
-    }
-
-    let name = "Head".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    _ = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        c7,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Left Clavical".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        c7,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Left Arm".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Left Forearm".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Left Hand".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    _ = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    prev_entity = c7;
-
-    let name = "Right Clavical".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Right Arm".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Right Forearm".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    prev_entity = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
-    bone_id += 1;
-
-    let name = "Right Hand".to_string();
-    let bone = skeleton_parts.get(&name).unwrap();
-    _ = spawn_bone(
-        &mut commands,
-        meshes,
-        material_handle.clone(),
-        materials,
-        bone,
-        bone_id,
-        prev_entity,
-        skeleton_id,
-    );
 }
 
 fn button_clicked(
@@ -822,16 +2254,52 @@ fn setup_ui(mut commands: Commands, my_assets: Res<YogaAssets>) {
                     margin: UiRect::all(Val::Px(5.0)),
                     ..default()
                 },
-                children![(
-                    AsanaName,
-                    Text::new("YogaMat Lives!"),
-                    TextFont {
-                        font: my_assets.font.clone(),
-                        font_size: 30.0,
-                        ..default()
-                    },
-                    TextColor(my_assets.font_color),
-                )],
+                children![
+                    (
+                        AsanaName,
+                        Text::new("YogaMat Lives!"),
+                        TextFont {
+                            font: my_assets.font.clone(),
+                            font_size: 30.0,
+                            ..default()
+                        },
+                        TextColor(my_assets.font_color),
+                    ),
+                    (
+                        // Keeps its size so the text doesn't move as the ring breathes.
+                        Node {
+                            width: Val::Px(BREATH_RING.1),
+                            height: Val::Px(BREATH_RING.1),
+                            justify_content: JustifyContent::Center, // horizontally
+                            align_items: AlignItems::Center,         // vertically
+                            margin: UiRect::horizontal(Val::Px(15.0)),
+                            ..default()
+                        },
+                        children![(
+                            BreathRing,
+                            Node {
+                                width: Val::Px(BREATH_RING.0),
+                                height: Val::Px(BREATH_RING.0),
+                                border: UiRect::all(Val::Px(3.0)),
+                                ..default()
+                            },
+                            BorderColor(my_assets.font_color),
+                            BorderRadius::MAX,
+                            Visibility::Hidden,
+                        )],
+                    ),
+                    (
+                        BreathText,
+                        Text::new("Exhale"),
+                        TextFont {
+                            font: my_assets.font.clone(),
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(my_assets.font_color),
+                        Visibility::Hidden,
+                    ),
+                ],
             ));
             commands
                 .spawn((
@@ -0,0 +1,172 @@
+//! The same pose on the other side of the body.
+//!
+//! Mirroring swaps every left bone with its right counterpart and reflects
+//! the pose across the body's sagittal plane: the vertical plane through the
+//! hips that the hips are level across, or, with the hips on their side, the
+//! one they face along. The hips stay where they are and turn the other way.
+//! Every other joint is relative to a bone whose own sagittal plane is
+//! x = 0, which is how the left and right bone frames of the database mirror
+//! each other.
+
+use bevy::math::{Mat3, Mat4, Vec3};
+
+use crate::{
+    kinematics,
+    skeleton::{self, Joint},
+    Asana, AsanaData,
+};
+
+/// Appended to the names of mirrored asanas.
+pub const SUFFIX: &str = " (other side)";
+
+/// Largest distance, in skeleton units, between a joint and where the
+/// mirrored pose puts it that still counts as symmetric.
+pub const SYMMETRY_TOLERANCE: f32 = 6.0;
+
+/// Below this the hips' left to right axis counts as vertical.
+const ON_THEIR_SIDE: f32 = 0.5;
+
+/// Normal of the sagittal plane for hips placed by `hips`, horizontal.
+fn sagittal_normal(hips: Mat4) -> Vec3 {
+    let across = hips.x_axis.truncate().reject_from(Vec3::Y);
+    if across.length() > ON_THEIR_SIDE {
+        return across.normalize();
+    }
+    hips.z_axis
+        .truncate()
+        .reject_from(Vec3::Y)
+        .try_normalize()
+        .unwrap_or(Vec3::Z)
+}
+
+/// `mat` of `joint_id` reflected across the sagittal plane. It poses the
+/// joint's counterpart, `skeleton::opposite(joint_id)`.
+pub fn mirror_matrix(joint_id: i32, mat: Mat4) -> Mat4 {
+    let in_bone = Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0));
+    if skeleton::parent(joint_id).is_some() {
+        return in_bone * mat * in_bone;
+    }
+    let normal = sagittal_normal(mat);
+    let reflection = Mat3::IDENTITY - 2.0 * Mat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z);
+    Mat4 {
+        w_axis: mat.w_axis,
+        ..Mat4::from_mat3(reflection) * mat * in_bone
+    }
+}
+
+/// Parent-relative joint matrices, as `pose::pose_matrices` returns them,
+/// for the other side. Joints whose counterpart is missing are left out.
+pub fn mirror_matrices(matrices: &[(i32, Mat4)]) -> Vec<(i32, Mat4)> {
+    matrices
+        .iter()
+        .filter_map(|(joint_id, _mat)| {
+            let opposite = skeleton::opposite(*joint_id);
+            matrices
+                .iter()
+                .find(|(other_id, _mat)| *other_id == opposite)
+                .map(|(_other_id, mat)| (*joint_id, mirror_matrix(opposite, *mat)))
+        })
+        .collect()
+}
+
+/// `joint` reflected onto its counterpart. Stored angles carry over with
+/// rotation and abduction negated; the hips' are recomputed.
+pub fn mirror_joint(joint: &Joint) -> Joint {
+    let mut mirrored = Joint::from_matrix(
+        skeleton::opposite(joint.joint_id),
+        joint.pose_id,
+        &mirror_matrix(joint.joint_id, joint.matrix()),
+    );
+    if skeleton::parent(joint.joint_id).is_some() {
+        mirrored.angles = joint.angles.map(|[x, y, z]| [x, -y, -z]);
+    }
+    mirrored
+}
+
+/// Every joint of `joints` mirrored, in joint id order.
+pub fn mirror_pose(joints: &[Joint]) -> Vec<Joint> {
+    let mut mirrored = joints.iter().map(mirror_joint).collect::<Vec<Joint>>();
+    mirrored.sort_by_key(|joint| joint.joint_id);
+    mirrored
+}
+
+/// Largest distance, in skeleton units, from a joint of `joints` to where
+/// the mirrored pose puts it. Near zero for a pose that is the same on both
+/// sides. `None` when the pose misses a joint.
+pub fn asymmetry(joints: &[Joint]) -> Option<f32> {
+    let bones = skeleton::bones();
+    let original = kinematics::forward_kinematics(bones, Mat4::IDENTITY, joints).ok()?;
+    let mirrored = kinematics::forward_kinematics(bones, Mat4::IDENTITY, &mirror_pose(joints)).ok()?;
+    Some(
+        original
+            .bones
+            .iter()
+            .zip(mirrored.bones.iter())
+            .map(|(bone, other)| bone.start.distance(other.start))
+            .fold(0.0, f32::max),
+    )
+}
+
+/// Asanas whose pose differs from side to side by more than
+/// `SYMMETRY_TOLERANCE`, in the order of `data.asanas`.
+pub fn asymmetric(data: &AsanaData) -> Vec<i32> {
+    data.asanas
+        .iter()
+        .filter(|asana| {
+            data.poses
+                .get(&asana.pose_id)
+                .and_then(|joints| asymmetry(joints))
+                .is_some_and(|asymmetry| asymmetry > SYMMETRY_TOLERANCE)
+        })
+        .map(|asana| asana.asana_id)
+        .collect()
+}
+
+/// Adds the other side of each asana in `asana_ids` as a new asana with its
+/// own pose, named with `SUFFIX`. Asanas whose other side is already there,
+/// or that are mirrored variants themselves, are skipped. Returns the
+/// (original, mirrored) asana id pairs added.
+pub fn add_mirrored(data: &mut AsanaData, asana_ids: &[i32]) -> Vec<(i32, i32)> {
+    let mut next_asana_id = data.asanas.iter().map(|asana| asana.asana_id).max().unwrap_or(0) + 1;
+    let mut next_pose_id = data
+        .asanas
+        .iter()
+        .map(|asana| asana.pose_id)
+        .chain(data.poses.keys().copied())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut added = Vec::new();
+    for asana_id in asana_ids {
+        let Some(asana) = data.asanas.iter().find(|asana| asana.asana_id == *asana_id) else {
+            continue;
+        };
+        let sanskrit = format!("{}{SUFFIX}", asana.sanskrit.trim());
+        if asana.sanskrit.ends_with(SUFFIX) || data.asanas.iter().any(|other| other.sanskrit == sanskrit) {
+            continue;
+        }
+        let Some(joints) = data.poses.get(&asana.pose_id) else {
+            continue;
+        };
+        let mut joints = mirror_pose(joints);
+        for joint in joints.iter_mut() {
+            joint.pose_id = next_pose_id;
+        }
+        let mirrored = Asana {
+            asana_id: next_asana_id,
+            pose_id: next_pose_id,
+            sanskrit,
+            english: format!("{}{SUFFIX}", asana.english.trim()),
+            unicode: asana.unicode.as_ref().map(|unicode| format!("{unicode}{SUFFIX}")),
+            notes: asana.notes.clone(),
+            category_ids: asana.category_ids.clone(),
+            dristi: asana.dristi.map(|dristi| dristi.mirrored()),
+        };
+        data.poses.insert(next_pose_id, joints);
+        data.asanas.push(mirrored);
+        added.push((*asana_id, next_asana_id));
+        next_asana_id += 1;
+        next_pose_id += 1;
+    }
+    added
+}
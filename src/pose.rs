@@ -0,0 +1,95 @@
+//! Looking up the joint matrices a skeleton is posed with.
+
+use std::fmt;
+
+use bevy::math::Mat4;
+
+use crate::{skeleton::JointMatrix, Asana, AsanaData};
+
+/// Asana id of Tadasana, standing at rest. The smooth body is bound in it
+/// and `validate::repair` copies missing joints from it.
+pub const TADASANA: i32 = 152;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoseError {
+    AsanaNotFound(i32),
+    PoseMissing { sanskrit: String, pose_id: i32 },
+    JointMissing { sanskrit: String, joint_id: i32 },
+    /// A NaN or infinite value, which `Transform::from_matrix` can't take.
+    BadJoint { sanskrit: String, joint_id: i32 },
+}
+
+impl fmt::Display for PoseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoseError::AsanaNotFound(asana_id) => write!(f, "no asana has id {asana_id}"),
+            PoseError::PoseMissing { sanskrit, pose_id } => {
+                write!(f, "{sanskrit}: pose {pose_id} is missing")
+            }
+            PoseError::JointMissing { sanskrit, joint_id } => {
+                write!(f, "{sanskrit}: joint {joint_id} is missing")
+            }
+            PoseError::BadJoint { sanskrit, joint_id } => {
+                write!(f, "{sanskrit}: joint {joint_id} has a NaN or infinite value")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PoseError {}
+
+/// The asana with `asana_id`. Names aren't unique, a mirrored or imported
+/// asana can share one with the asana it came from.
+pub fn find_asana(data: &AsanaData, asana_id: i32) -> Result<&Asana, PoseError> {
+    data.asanas
+        .iter()
+        .find(|asana| asana.asana_id == asana_id)
+        .ok_or(PoseError::AsanaNotFound(asana_id))
+}
+
+/// The joint matrices of `asana`'s pose.
+pub fn load_pose(data: &AsanaData, asana: &Asana) -> Result<Vec<JointMatrix>, PoseError> {
+    let joints = data
+        .poses
+        .get(&asana.pose_id)
+        .ok_or_else(|| PoseError::PoseMissing {
+            sanskrit: asana.sanskrit.clone(),
+            pose_id: asana.pose_id,
+        })?;
+    Ok(joints
+        .iter()
+        .map(|joint| JointMatrix {
+            mat: joint.matrix(),
+            joint_id: joint.joint_id,
+        })
+        .collect::<Vec<JointMatrix>>())
+}
+
+/// A matrix for every joint in `joint_ids`, so a skeleton is either posed
+/// completely or left as it is.
+pub fn pose_matrices(
+    data: &AsanaData,
+    asana: &Asana,
+    joint_ids: &[i32],
+) -> Result<Vec<(i32, Mat4)>, PoseError> {
+    let pose = load_pose(data, asana)?;
+    joint_ids
+        .iter()
+        .map(|joint_id| {
+            let joint = pose
+                .iter()
+                .find(|joint| joint.joint_id == *joint_id)
+                .ok_or_else(|| PoseError::JointMissing {
+                    sanskrit: asana.sanskrit.clone(),
+                    joint_id: *joint_id,
+                })?;
+            if !joint.mat.is_finite() {
+                return Err(PoseError::BadJoint {
+                    sanskrit: asana.sanskrit.clone(),
+                    joint_id: *joint_id,
+                });
+            }
+            Ok((*joint_id, joint.mat))
+        })
+        .collect()
+}
@@ -0,0 +1,197 @@
+//! Bodies other than the one the poses were made on.
+//!
+//! Retargeting keeps every joint rotation and moves each joint origin,
+//! which sits at the end of its parent bone, by how much that bone grew.
+
+use bevy::math::Mat4;
+
+use crate::skeleton::{self, Joint, Segment, SkeletonBone};
+
+/// Joint ids the standing height is measured along, crown to ankle.
+const HEAD: i32 = 32;
+const LEFT_CALF: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyProportions {
+    /// Ankle to crown in skeleton units, the default body is 95 tall.
+    pub height: f32,
+    /// Length of each segment relative to the default body, before the
+    /// whole body is scaled to `height`.
+    pub pelvis: f32,
+    pub thigh: f32,
+    pub shin: f32,
+    pub foot: f32,
+    pub torso: f32,
+    pub neck: f32,
+    pub head: f32,
+    pub shoulder: f32,
+    pub upper_arm: f32,
+    pub forearm: f32,
+    pub hand: f32,
+}
+
+impl Default for BodyProportions {
+    fn default() -> Self {
+        BodyProportions::ADULT
+    }
+}
+
+impl BodyProportions {
+    /// The body the poses were made on.
+    pub const ADULT: BodyProportions = BodyProportions {
+        height: 95.0,
+        pelvis: 1.0,
+        thigh: 1.0,
+        shin: 1.0,
+        foot: 1.0,
+        torso: 1.0,
+        neck: 1.0,
+        head: 1.0,
+        shoulder: 1.0,
+        upper_arm: 1.0,
+        forearm: 1.0,
+        hand: 1.0,
+    };
+
+    /// About eight years old: a big head and short limbs.
+    pub const CHILD: BodyProportions = BodyProportions {
+        height: 66.0,
+        pelvis: 0.9,
+        thigh: 0.88,
+        shin: 0.86,
+        foot: 0.9,
+        torso: 1.0,
+        neck: 0.9,
+        head: 1.45,
+        shoulder: 0.9,
+        upper_arm: 0.92,
+        forearm: 0.9,
+        hand: 0.95,
+    };
+
+    pub const TALL: BodyProportions = BodyProportions {
+        height: 110.0,
+        thigh: 1.06,
+        shin: 1.08,
+        upper_arm: 1.04,
+        forearm: 1.05,
+        ..BodyProportions::ADULT
+    };
+
+    pub const LONG_TORSO: BodyProportions = BodyProportions {
+        torso: 1.2,
+        thigh: 0.92,
+        shin: 0.92,
+        ..BodyProportions::ADULT
+    };
+
+    pub const PRESETS: [(&'static str, BodyProportions); 4] = [
+        ("Adult", BodyProportions::ADULT),
+        ("Child", BodyProportions::CHILD),
+        ("Tall", BodyProportions::TALL),
+        ("Long torso", BodyProportions::LONG_TORSO),
+    ];
+
+    pub fn ratio(&self, segment: Segment) -> f32 {
+        match segment {
+            Segment::Pelvis => self.pelvis,
+            Segment::Thigh => self.thigh,
+            Segment::Shin => self.shin,
+            Segment::Foot => self.foot,
+            Segment::Torso => self.torso,
+            Segment::Neck => self.neck,
+            Segment::Head => self.head,
+            Segment::Shoulder => self.shoulder,
+            Segment::UpperArm => self.upper_arm,
+            Segment::Forearm => self.forearm,
+            Segment::Hand => self.hand,
+        }
+    }
+
+    /// Length of the bones from the ankle up to the head with the ratios
+    /// applied, before scaling to `height`.
+    fn unscaled_height(&self) -> f32 {
+        let mut chain = skeleton::ancestors(HEAD);
+        chain.extend(skeleton::ancestors(LEFT_CALF));
+        skeleton::bones()
+            .iter()
+            .filter(|bone| bone.parent.is_some() && chain.contains(&bone.joint_id))
+            .map(|bone| bone.cube.y * self.ratio(bone.segment))
+            .sum()
+    }
+
+    /// Scale that brings the body with its ratios to `height`, and how much
+    /// wider every bone gets. 1 for `ADULT`.
+    pub fn overall_scale(&self) -> f32 {
+        let adult = BodyProportions::ADULT;
+        self.height / adult.height * adult.unscaled_height() / self.unscaled_height()
+    }
+
+    pub fn segment_scale(&self, segment: Segment) -> f32 {
+        self.overall_scale() * self.ratio(segment)
+    }
+
+    /// How much the origin of `joint_id` moves away from its parent joint.
+    /// The hips keep their height above the feet, so they follow the legs.
+    pub fn origin_scale(&self, joint_id: i32) -> f32 {
+        match skeleton::parent(joint_id).and_then(skeleton::find_bone) {
+            Some(parent) => self.segment_scale(parent.segment),
+            None => self.overall_scale() * (self.thigh + self.shin) / 2.0,
+        }
+    }
+
+    /// The skeleton with this body, in the same order as `skeleton::bones`.
+    pub fn bones(&self) -> Vec<SkeletonBone> {
+        let overall = self.overall_scale();
+        skeleton::bones()
+            .iter()
+            .map(|bone| {
+                let width = if bone.segment == Segment::Head {
+                    overall * self.head
+                } else {
+                    overall
+                };
+                SkeletonBone {
+                    cube: bone.cube.scaled(self.segment_scale(bone.segment), width),
+                    ..bone.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// `joint` of a pose made on the default body, moved onto this one.
+    pub fn retarget(&self, joint: &Joint) -> Joint {
+        self.scale_origin(joint, self.origin_scale(joint.joint_id))
+    }
+
+    /// Inverse of `retarget`, for saving a pose edited on this body.
+    pub fn to_default_body(&self, joint: &Joint) -> Joint {
+        self.scale_origin(joint, 1.0 / self.origin_scale(joint.joint_id))
+    }
+
+    fn scale_origin(&self, joint: &Joint, scale: f32) -> Joint {
+        Joint {
+            origin_x: joint.origin_x * scale,
+            origin_y: joint.origin_y * scale,
+            origin_z: joint.origin_z * scale,
+            ..joint.clone()
+        }
+    }
+
+    /// `retarget` for a joint matrix.
+    pub fn retarget_matrix(&self, joint_id: i32, mat: Mat4) -> Mat4 {
+        scale_matrix_origin(mat, self.origin_scale(joint_id))
+    }
+
+    /// `to_default_body` for a joint matrix.
+    pub fn to_default_body_matrix(&self, joint_id: i32, mat: Mat4) -> Mat4 {
+        scale_matrix_origin(mat, 1.0 / self.origin_scale(joint_id))
+    }
+}
+
+fn scale_matrix_origin(mat: Mat4, scale: f32) -> Mat4 {
+    Mat4 {
+        w_axis: (mat.w_axis.truncate() * scale).extend(1.0),
+        ..mat
+    }
+}
@@ -0,0 +1,364 @@
+//! Flows of asanas, played one after the other.
+//!
+//! A step moves into its asana over its transition time, then holds it. Paced
+//! by breath instead, a step lasts its inhales and exhales and the move into
+//! the asana takes the first of them. Each step starts on whichever of its
+//! inhales and exhales it has more of, an inhale if neither, and alternates
+//! from there. The built-in sequences name asanas by their ids in the pose
+//! database.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub asana_id: i32,
+    /// Seconds spent in the asana once it's reached.
+    pub hold: f32,
+    /// Seconds taken to move into the asana from the one before.
+    pub transition: f32,
+    /// Shown mirrored, for the left side of asanas stored for the right.
+    #[serde(default)]
+    pub other_side: bool,
+    /// Breaths in and out taken over the step when paced by breath.
+    #[serde(default)]
+    pub inhales: u32,
+    #[serde(default)]
+    pub exhales: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sequence {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+impl Sequence {
+    /// Seconds from the start to the end of the last hold.
+    pub fn duration(&self) -> f32 {
+        self.steps.iter().map(Step::duration).sum()
+    }
+}
+
+impl Step {
+    pub fn duration(&self) -> f32 {
+        self.transition + self.hold
+    }
+
+    /// Inhales and exhales, at least one.
+    pub fn phases(&self) -> u32 {
+        (self.inhales + self.exhales).max(1)
+    }
+
+    /// Whether the step's `breath`th breath, from 0, is an inhale or an
+    /// exhale. `None` past its last one.
+    pub fn phase(&self, breath: u32) -> Option<Phase> {
+        if breath >= self.inhales + self.exhales {
+            return None;
+        }
+        let (mut inhales, mut exhales) = (self.inhales, self.exhales);
+        let mut phase = None;
+        for _ in 0..=breath {
+            let next = match phase {
+                Some(Phase::Inhale) if exhales > 0 => Phase::Exhale,
+                Some(Phase::Exhale) if inhales > 0 => Phase::Inhale,
+                Some(same) => same,
+                None if exhales > inhales => Phase::Exhale,
+                None => Phase::Inhale,
+            };
+            match next {
+                Phase::Inhale => inhales -= 1,
+                Phase::Exhale => exhales -= 1,
+            }
+            phase = Some(next);
+        }
+        phase
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pacing {
+    /// Steps take their transition and hold times.
+    Seconds,
+    /// Steps take their inhales and exhales, breathing this many times a
+    /// minute.
+    Breath { per_minute: f32 },
+}
+
+impl Pacing {
+    /// Seconds of `step` spent moving into its asana and holding it. A
+    /// breath rate that isn't above 0 falls back on the step's own times.
+    pub fn timing(&self, step: &Step) -> (f32, f32) {
+        match self.phase() {
+            Some(phase) => (phase, phase * (step.phases() - 1) as f32),
+            None => (step.transition, step.hold),
+        }
+    }
+
+    /// Seconds of an inhale or an exhale when paced by a usable breath rate.
+    fn phase(&self) -> Option<f32> {
+        match *self {
+            Pacing::Breath { per_minute } if per_minute.is_finite() && per_minute > 0.0 => {
+                Some(30.0 / per_minute)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Inhale,
+    Exhale,
+}
+
+const SAMASTHITI: i32 = 106;
+const URDHVA_HASTASANA: i32 = 150;
+const UTTANASANA: i32 = 72;
+const ARDHA_UTTANASANA: i32 = 16;
+const CHATURANGA_DANDASANA: i32 = 6;
+const URDHVA_MUKHA_SVANASANA: i32 = 7;
+const ADHO_MUKHA_SVANASANA: i32 = 8;
+const UTKATASANA: i32 = 19;
+const VIRABHADRASANA_A: i32 = 17;
+
+/// Seconds of one breath, each movement of a salutation takes one.
+const BREATH: f32 = 4.0;
+
+/// A step moved into on one breath and left on the next, taking `inhales`
+/// and `exhales` when paced by breath.
+fn breaths(asana_id: i32, inhales: u32, exhales: u32) -> Step {
+    Step {
+        asana_id,
+        hold: BREATH / 2.0,
+        transition: BREATH / 2.0,
+        other_side: false,
+        inhales,
+        exhales,
+    }
+}
+
+fn inhale(asana_id: i32) -> Step {
+    breaths(asana_id, 1, 0)
+}
+
+fn exhale(asana_id: i32) -> Step {
+    breaths(asana_id, 0, 1)
+}
+
+/// Downward dog at the end of a round: moved into on an exhale and held for
+/// five breaths.
+fn five_breaths(asana_id: i32) -> Step {
+    Step {
+        hold: 5.0 * BREATH,
+        ..breaths(asana_id, 5, 6)
+    }
+}
+
+fn other_side(step: Step) -> Step {
+    Step {
+        other_side: true,
+        ..step
+    }
+}
+
+pub fn surya_namaskar_a() -> Sequence {
+    Sequence {
+        name: "Surya Namaskar A".to_string(),
+        steps: vec![
+            breaths(SAMASTHITI, 1, 1),
+            inhale(URDHVA_HASTASANA),
+            exhale(UTTANASANA),
+            inhale(ARDHA_UTTANASANA),
+            exhale(CHATURANGA_DANDASANA),
+            inhale(URDHVA_MUKHA_SVANASANA),
+            five_breaths(ADHO_MUKHA_SVANASANA),
+            inhale(ARDHA_UTTANASANA),
+            exhale(UTTANASANA),
+            inhale(URDHVA_HASTASANA),
+            exhale(SAMASTHITI),
+        ],
+    }
+}
+
+/// Warrior A is stepped into with the right foot forward, then the left.
+pub fn surya_namaskar_b() -> Sequence {
+    let vinyasa = [
+        exhale(CHATURANGA_DANDASANA),
+        inhale(URDHVA_MUKHA_SVANASANA),
+        exhale(ADHO_MUKHA_SVANASANA),
+    ];
+    let mut steps = vec![
+        breaths(SAMASTHITI, 1, 1),
+        inhale(UTKATASANA),
+        exhale(UTTANASANA),
+        inhale(ARDHA_UTTANASANA),
+    ];
+    steps.extend(vinyasa.clone());
+    steps.push(inhale(VIRABHADRASANA_A));
+    steps.extend(vinyasa.clone());
+    steps.push(other_side(inhale(VIRABHADRASANA_A)));
+    steps.extend(vinyasa[..2].iter().cloned());
+    steps.extend([
+        five_breaths(ADHO_MUKHA_SVANASANA),
+        inhale(ARDHA_UTTANASANA),
+        exhale(UTTANASANA),
+        inhale(UTKATASANA),
+        exhale(SAMASTHITI),
+    ]);
+    Sequence {
+        name: "Surya Namaskar B".to_string(),
+        steps,
+    }
+}
+
+/// The sequences that come with the app.
+pub fn built_in() -> Vec<Sequence> {
+    vec![surya_namaskar_a(), surya_namaskar_b()]
+}
+
+/// Where a sequence being played has got to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    sequence: Sequence,
+    step: usize,
+    /// Seconds since the current step started, its transition then its hold.
+    elapsed: f32,
+    playing: bool,
+    pacing: Pacing,
+}
+
+impl Player {
+    /// A player paused before the first step.
+    pub fn new(sequence: Sequence) -> Player {
+        Player {
+            sequence,
+            step: 0,
+            elapsed: 0.0,
+            playing: false,
+            pacing: Pacing::Seconds,
+        }
+    }
+
+    pub fn pacing(&self) -> Pacing {
+        self.pacing
+    }
+
+    pub fn set_pacing(&mut self, pacing: Pacing) {
+        self.pacing = pacing;
+    }
+
+    fn duration(&self, step: &Step) -> f32 {
+        let (transition, hold) = self.pacing.timing(step);
+        transition + hold
+    }
+
+    /// Seconds the current step takes to move into its asana.
+    pub fn transition(&self) -> f32 {
+        self.current().map_or(0.0, |step| self.pacing.timing(step).0)
+    }
+
+    /// The breath being taken and how far through it, from 0 to 1, when
+    /// paced by breath.
+    pub fn breath(&self) -> Option<(Phase, f32)> {
+        let phase_seconds = self.pacing.phase()?;
+        let step = self.current()?;
+        let into_step = self.elapsed / phase_seconds;
+        let within = (into_step.floor() as u32).min(step.phases() - 1);
+        let phase = step.phase(within)?;
+        Some((phase, (into_step - within as f32).clamp(0.0, 1.0)))
+    }
+
+    pub fn sequence(&self) -> &Sequence {
+        &self.sequence
+    }
+
+    /// Index of the current step.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn current(&self) -> Option<&Step> {
+        self.sequence.steps.get(self.step)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    fn is_finished(&self) -> bool {
+        self.step + 1 >= self.sequence.steps.len()
+            && self.current().is_none_or(|step| self.elapsed >= self.duration(step))
+    }
+
+    /// Plays on from where it was paused, or from the start once finished.
+    /// The step to move into, when that hasn't started yet.
+    pub fn play(&mut self) -> Option<usize> {
+        if self.sequence.steps.is_empty() {
+            return None;
+        }
+        if self.is_finished() {
+            self.step = 0;
+            self.elapsed = 0.0;
+        }
+        self.playing = true;
+        (self.elapsed == 0.0).then_some(self.step)
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Moves on by `seconds` while playing. The step moved into, if it
+    /// changed; the player pauses at the end of the last one.
+    pub fn advance(&mut self, seconds: f32) -> Option<usize> {
+        if !self.playing {
+            return None;
+        }
+        self.elapsed += seconds;
+        let mut moved = false;
+        while let Some(step) = self.current() {
+            let duration = self.duration(step);
+            if self.elapsed < duration {
+                break;
+            }
+            if self.step + 1 == self.sequence.steps.len() {
+                self.elapsed = duration;
+                self.playing = false;
+                break;
+            }
+            self.elapsed -= duration;
+            self.step += 1;
+            moved = true;
+        }
+        moved.then_some(self.step)
+    }
+
+    /// Skips to the start of the next step, if there is one.
+    pub fn next_step(&mut self) -> Option<usize> {
+        (self.step + 1 < self.sequence.steps.len()).then(|| self.go_to(self.step + 1))
+    }
+
+    /// Goes back to the start of the step before, if there is one.
+    pub fn previous_step(&mut self) -> Option<usize> {
+        (self.step > 0).then(|| self.go_to(self.step - 1))
+    }
+
+    fn go_to(&mut self, step: usize) -> usize {
+        self.step = step;
+        self.elapsed = 0.0;
+        step
+    }
+
+    /// How far through the whole sequence it is, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        let total = self.sequence.steps.iter().map(|step| self.duration(step)).sum::<f32>();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let done = self.sequence.steps[..self.step]
+            .iter()
+            .map(|step| self.duration(step))
+            .sum::<f32>();
+        ((done + self.elapsed) / total).clamp(0.0, 1.0)
+    }
+}
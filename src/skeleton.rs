@@ -1,8 +1,11 @@
-use std::collections::HashMap;
+use std::sync::LazyLock;
+
 use bevy::{prelude::*, render::{render_asset::RenderAssetUsages, render_resource::PrimitiveTopology}};
 use bincode::{Decode, Encode};
 use serde::{Serialize, Deserialize};
+use crate::limits::{self, RangeOfMotion};
 
+#[derive(Debug)]
 pub struct JointMatrix {
     pub mat: Mat4,
     pub joint_id: i32,
@@ -20,7 +23,41 @@ pub struct BoneCube {
     pub name: String,
 }
 
-#[derive(Default, Serialize, Deserialize, Decode, Encode)]
+impl BoneCube {
+    /// Width (x) and depth (z) at the joint end.
+    pub fn top_size(&self) -> Vec2 {
+        Vec2::new(self.x_top, self.z_top)
+    }
+
+    /// Width (x) and depth (z) at the far end.
+    pub fn bottom_size(&self) -> Vec2 {
+        Vec2::new(self.x_bottom, self.z_bottom)
+    }
+
+    /// Volume of the block between the two ends, ignoring the bevels.
+    pub fn volume(&self) -> f32 {
+        let middle = (self.top_size() + self.bottom_size()) / 2.0;
+        let areas = self.top_size().element_product() + 4.0 * middle.element_product() + self.bottom_size().element_product();
+        self.y / 6.0 * areas
+    }
+
+    /// The same bone `length` times as long and `width` times as wide,
+    /// hanging from the same joint.
+    pub fn scaled(&self, length: f32, width: f32) -> BoneCube {
+        BoneCube {
+            x_top: self.x_top * width,
+            x_bottom: self.x_bottom * width,
+            z_top: self.z_top * width,
+            z_bottom: self.z_bottom * width,
+            y: self.y * length,
+            inset: self.inset * width,
+            transform: Transform::from_translation(self.transform.translation * length),
+            name: self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Decode, Encode)]
 pub struct Joint {
     pub joint_id: i32,
     pub pose_id: i32,
@@ -33,6 +70,31 @@ pub struct Joint {
     pub origin_x: f32,
     pub origin_y: f32,
     pub origin_z: f32,
+    /// Parent-relative Euler angles in degrees, in `EULER_ORDER`, when the
+    /// data came with them. `euler_degrees` computes them otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub angles: Option<[f32; 3]>,
+}
+
+/// Joint angles are rotations about x, then the rotated y, then the rotated z.
+pub const EULER_ORDER: EulerRot = EulerRot::XYZ;
+
+/// Largest difference between two rotations, in degrees, that still counts as the same.
+const ANGLE_TOLERANCE: f32 = 1.0;
+
+/// `rotation` as Euler angles in degrees.
+pub fn euler_degrees(rotation: Quat) -> Vec3 {
+    let (x, y, z) = rotation.to_euler(EULER_ORDER);
+    Vec3::new(x.to_degrees(), y.to_degrees(), z.to_degrees())
+}
+
+pub fn rotation_from_degrees(angles: Vec3) -> Quat {
+    Quat::from_euler(
+        EULER_ORDER,
+        angles.x.to_radians(),
+        angles.y.to_radians(),
+        angles.z.to_radians(),
+    )
 }
 
 impl Joint {
@@ -60,10 +122,64 @@ impl Joint {
                 1.0),
         }
     }
+
+    /// Inverse of `matrix`, used to turn an edited bone `Transform` back into a joint.
+    pub fn from_matrix(joint_id: i32, pose_id: i32, mat: &Mat4) -> Joint {
+        Joint {
+            joint_id,
+            pose_id,
+            up_x: mat.y_axis.x,
+            up_y: mat.y_axis.y,
+            up_z: mat.y_axis.z,
+            forward_x: mat.z_axis.x,
+            forward_y: mat.z_axis.y,
+            forward_z: mat.z_axis.z,
+            origin_x: mat.w_axis.x,
+            origin_y: mat.w_axis.y,
+            origin_z: mat.w_axis.z,
+            angles: None,
+        }
+    }
+
+    /// Rotation relative to the parent bone, with drifted axes straightened out.
+    pub fn rotation(&self) -> Quat {
+        let up = Vec3::new(self.up_x, self.up_y, self.up_z).normalize_or(Vec3::Y);
+        let forward = Vec3::new(self.forward_x, self.forward_y, self.forward_z)
+            .reject_from_normalized(up)
+            .normalize_or(up.any_orthonormal_vector());
+        Quat::from_mat3(&Mat3::from_cols(up.cross(forward), up, forward))
+    }
+
+    /// Parent-relative Euler angles in degrees, in `EULER_ORDER`.
+    pub fn euler_degrees(&self) -> Vec3 {
+        self.angles
+            .map(Vec3::from)
+            .unwrap_or_else(|| euler_degrees(self.rotation()))
+    }
+
+    /// Turns the joint to `angles` (degrees, `EULER_ORDER`), keeping its origin.
+    pub fn set_euler_degrees(&mut self, angles: Vec3) {
+        let rotation = rotation_from_degrees(angles);
+        let up = rotation * Vec3::Y;
+        let forward = rotation * Vec3::Z;
+        (self.up_x, self.up_y, self.up_z) = (up.x, up.y, up.z);
+        (self.forward_x, self.forward_y, self.forward_z) = (forward.x, forward.y, forward.z);
+        self.angles = Some(angles.into());
+    }
+
+    /// Whether `angles` describe the joint's rotation.
+    pub fn angles_match(&self, angles: Vec3) -> bool {
+        rotation_from_degrees(angles)
+            .angle_between(self.rotation())
+            .to_degrees()
+            < ANGLE_TOLERANCE
+    }
 }
 
+/// The vertices of the mesh `make_bone_mesh` builds for `cube`, in the
+/// space of the bone's joint.
 #[rustfmt::skip]
-pub fn make_bone_mesh(cube: &BoneCube) -> Mesh {
+pub fn bone_corners(cube: &BoneCube) -> Vec<[f32; 3]> {
     let mut corners = Vec::new();
     let x_top = cube.x_top;
     let x_bottom = cube.x_bottom;
@@ -115,6 +231,12 @@ pub fn make_bone_mesh(cube: &BoneCube) -> Mesh {
         corner[1] += cube.transform.translation.y;
         corner[2] += cube.transform.translation.z;
     }
+    corners
+}
+
+#[rustfmt::skip]
+pub fn make_bone_mesh(cube: &BoneCube) -> Mesh {
+    let corners = bone_corners(cube);
 
 	let indices: [usize; 132] /*[108+24]*/ = [
 		// 12 faces
@@ -163,306 +285,284 @@ pub fn make_bone_mesh(cube: &BoneCube) -> Mesh {
     mesh
 }
 
-pub fn skelly() -> HashMap<String, BoneCube> {
-    let head_length = 11.0;
-    let clavical_lengh = 9.5;
-    let c_spine_length = 8.0;
-    let t_spine_length = 19.0;
-    let l_spine_length = 9.0;
-    let hip_length = 12.0; //6.0; // half transofrm y was -4.0
-    let femur_length = 29.0;
-    let calf_length = 26.0;
-    let foot_length = 15.0;
-    let humerus_length = 19.0;
-    let forearm_length = 17.0;
-    let hand_length = 11.0;
-    let the_inset = 0.75;
-    let mut map = HashMap::new();
-
-    let mut name = "Hips".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 15.0,
-            x_bottom: 13.0,
-            z_top: 6.0,
-            z_bottom: 4.0,
-            y: hip_length,
-            inset: 1.0,
-            transform: Transform::from_xyz(0.0, -4.0, 0.0),
-            name,
-        },
-    );
-
-    name = "Left Femur".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 6.0,
-            x_bottom: 4.0,
-            z_top: 6.0,
-            z_bottom: 4.0,
-            y: femur_length,
-            inset: 1.25,
-            transform: Transform::from_xyz(0.0, -femur_length / 2.0, 0.0),
-            name,
-        },
-    );
-
-    name = "Right Femur".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 6.0,
-            x_bottom: 4.0,
-            z_top: 6.0,
-            z_bottom: 4.0,
-            y: femur_length,
-            inset: 1.25,
-            transform: Transform::from_xyz(0.0, -femur_length / 2.0, 0.0),
-            name,
-        },
-    );
+const HEAD_LENGTH: f32 = 11.0;
+const CLAVICAL_LENGTH: f32 = 9.5;
+const C_SPINE_LENGTH: f32 = 8.0;
+const T_SPINE_LENGTH: f32 = 19.0;
+const L_SPINE_LENGTH: f32 = 9.0;
+const HIP_LENGTH: f32 = 12.0;
+const FEMUR_LENGTH: f32 = 29.0;
+const CALF_LENGTH: f32 = 26.0;
+const FOOT_LENGTH: f32 = 15.0;
+const HUMERUS_LENGTH: f32 = 19.0;
+const FOREARM_LENGTH: f32 = 17.0;
+const HAND_LENGTH: f32 = 11.0;
+const THE_INSET: f32 = 0.75;
+
+/// Size of a `BoneCube`, see `make_bone_mesh`.
+#[derive(Debug, Clone, Copy)]
+pub struct BoneShape {
+    pub x_top: f32,
+    pub x_bottom: f32,
+    pub z_top: f32,
+    pub z_bottom: f32,
+    pub length: f32,
+    pub inset: f32,
+    /// Where the middle of the mesh sits on the joint's y axis.
+    pub offset: f32,
+}
 
-    name = "Left Calf".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 4.5,
-            x_bottom: 2.5,
-            z_top: 4.5,
-            z_bottom: 2.5,
-            y: calf_length,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -calf_length / 2.0, 0.0),
-            name,
-        },
-    );
+impl BoneShape {
+    /// A bone hanging down from its joint.
+    const fn hanging(x_top: f32, x_bottom: f32, z_top: f32, z_bottom: f32, length: f32, inset: f32) -> BoneShape {
+        BoneShape {
+            x_top,
+            x_bottom,
+            z_top,
+            z_bottom,
+            length,
+            inset,
+            offset: -length / 2.0,
+        }
+    }
 
-    name = "Right Calf".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 4.5,
-            x_bottom: 2.5,
-            z_top: 4.5,
-            z_bottom: 2.5,
-            y: calf_length,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -calf_length / 2.0, 0.0),
-            name,
-        },
-    );
+    /// A vertebra of a spine section `section_length` long made of `count` of them.
+    const fn vertebra(section_length: f32, count: i32) -> BoneShape {
+        BoneShape::hanging(3.0, 3.0, 3.0, 3.0, section_length / count as f32, 0.5)
+    }
+}
 
-    name = "Left Foot".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.5,
-            x_bottom: 6.0,
-            z_top: 3.5,
-            z_bottom: 2.0,
-            y: foot_length,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -foot_length / 2.0, 0.0),
-            name,
-        },
-    );
+/// Body part a bone belongs to, what `BodyProportions` scales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Pelvis,
+    Thigh,
+    Shin,
+    Foot,
+    /// Lumbar and thoracic vertebrae.
+    Torso,
+    Neck,
+    Head,
+    Shoulder,
+    UpperArm,
+    Forearm,
+    Hand,
+}
 
-    name = "Right Foot".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.5,
-            x_bottom: 6.0,
-            z_top: 3.5,
-            z_bottom: 2.0,
-            y: foot_length,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -foot_length / 2.0, 0.0),
-            name,
-        },
-    );
+/// One bone of the skeleton, or a run of `count` identical vertebrae.
+#[derive(Debug, Clone, Copy)]
+pub struct BoneDef {
+    pub name: &'static str,
+    pub segment: Segment,
+    /// Joint id of the bone, the first of the run for vertebrae.
+    pub joint_id: i32,
+    /// Joint id of the bone this one hangs from, `None` for the hips.
+    pub parent: Option<i32>,
+    pub shape: BoneShape,
+    /// Vertebrae in the run, named `"{name} {n}"` counting down from `count`.
+    pub count: i32,
+    /// Range of motion of the joint. For vertebrae the neutral rotation is
+    /// that of the first one, the rest carry straight on from it.
+    pub rom: RangeOfMotion,
+}
 
-    name = "Lumbar".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.0,
-            x_bottom: 3.0,
-            z_top: 3.0,
-            z_bottom: 3.0,
-            y: (l_spine_length / 5.0),
-            inset: 0.5,
-            transform: Transform::from_xyz(0.0, -(l_spine_length / 5.0) / 2.0, 0.0),
-            name,
-        },
-    );
+const fn bone(
+    name: &'static str,
+    segment: Segment,
+    joint_id: i32,
+    parent: i32,
+    shape: BoneShape,
+    rom: RangeOfMotion,
+) -> BoneDef {
+    BoneDef {
+        name,
+        segment,
+        joint_id,
+        parent: Some(parent),
+        shape,
+        count: 1,
+        rom,
+    }
+}
 
-    name = "Thoracic".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.0,
-            x_bottom: 3.0,
-            z_top: 3.0,
-            z_bottom: 3.0,
-            y: t_spine_length / 12.0,
-            inset: 0.5,
-            transform: Transform::from_xyz(0.0, -(t_spine_length / 12.0) / 2.0, 0.0),
-            name,
-        },
-    );
+const fn vertebrae(
+    name: &'static str,
+    segment: Segment,
+    joint_id: i32,
+    parent: i32,
+    section_length: f32,
+    count: i32,
+    rom: RangeOfMotion,
+) -> BoneDef {
+    BoneDef {
+        name,
+        segment,
+        joint_id,
+        parent: Some(parent),
+        shape: BoneShape::vertebra(section_length, count),
+        count,
+        rom,
+    }
+}
 
-    name = "Cervical".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.0,
-            x_bottom: 3.0,
-            z_top: 3.0,
-            z_bottom: 3.0,
-            y: (c_spine_length / 7.0),
-            inset: 0.5,
-            transform: Transform::from_xyz(0.0, -(c_spine_length / 7.0) / 2.0, 0.0),
-            name,
+/// The skeleton every pose is made for, parents before their children.
+/// Joint ids are the `jointID`s of the pose database.
+pub const SKELETON: [BoneDef; 19] = [
+    BoneDef {
+        name: "Hips",
+        segment: Segment::Pelvis,
+        joint_id: 1,
+        parent: None,
+        shape: BoneShape {
+            x_top: 15.0,
+            x_bottom: 13.0,
+            z_top: 6.0,
+            z_bottom: 4.0,
+            length: HIP_LENGTH,
+            inset: 1.0,
+            offset: -4.0,
         },
-    );
-
-    name = "Head".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
+        count: 1,
+        rom: limits::FREE,
+    },
+    bone("Left Femur", Segment::Thigh, 2, 1, BoneShape::hanging(6.0, 4.0, 6.0, 4.0, FEMUR_LENGTH, 1.25), limits::HIP),
+    bone("Left Calf", Segment::Shin, 3, 2, BoneShape::hanging(4.5, 2.5, 4.5, 2.5, CALF_LENGTH, THE_INSET), limits::KNEE),
+    bone("Left Foot", Segment::Foot, 4, 3, BoneShape::hanging(3.5, 6.0, 3.5, 2.0, FOOT_LENGTH, THE_INSET), limits::ANKLE),
+    bone("Right Femur", Segment::Thigh, 5, 1, BoneShape::hanging(6.0, 4.0, 6.0, 4.0, FEMUR_LENGTH, 1.25), limits::HIP.mirrored()),
+    bone("Right Calf", Segment::Shin, 6, 5, BoneShape::hanging(4.5, 2.5, 4.5, 2.5, CALF_LENGTH, THE_INSET), limits::KNEE.mirrored()),
+    bone("Right Foot", Segment::Foot, 7, 6, BoneShape::hanging(3.5, 6.0, 3.5, 2.0, FOOT_LENGTH, THE_INSET), limits::ANKLE.mirrored()),
+    vertebrae("Lumbar", Segment::Torso, 8, 1, L_SPINE_LENGTH, 5, limits::LUMBAR),
+    vertebrae("Thoracic", Segment::Torso, 13, 12, T_SPINE_LENGTH, 12, limits::THORACIC),
+    vertebrae("Cervical", Segment::Neck, 25, 24, C_SPINE_LENGTH, 7, limits::CERVICAL),
+    // The head and the shoulders hang from C7, the first cervical vertebra.
+    bone(
+        "Head",
+        Segment::Head,
+        32,
+        25,
+        BoneShape {
             x_top: 12.0,
             x_bottom: 12.0,
             z_top: 12.0,
             z_bottom: 12.0,
-            y: head_length,
+            length: HEAD_LENGTH,
             inset: 2.5,
-            transform: Transform::from_xyz(0.0, -head_length, 0.0),
-            name,
+            offset: -HEAD_LENGTH,
         },
-    );
+        limits::HEAD,
+    ),
+    bone("Left Clavical", Segment::Shoulder, 33, 25, BoneShape::hanging(2.0, 2.0, 2.0, 2.0, CLAVICAL_LENGTH, THE_INSET), limits::CLAVICAL),
+    bone("Left Arm", Segment::UpperArm, 34, 33, BoneShape::hanging(4.20, 3.25, 5.075, 3.5, HUMERUS_LENGTH, 1.0), limits::SHOULDER),
+    bone("Left Forearm", Segment::Forearm, 35, 34, BoneShape::hanging(3.75, 2.75, 3.75, 2.75, FOREARM_LENGTH, 1.0), limits::ELBOW),
+    bone("Left Hand", Segment::Hand, 36, 35, BoneShape::hanging(3.0, 2.0, 4.0, 5.0, HAND_LENGTH, THE_INSET), limits::WRIST),
+    bone("Right Clavical", Segment::Shoulder, 37, 25, BoneShape::hanging(2.0, 2.0, 2.0, 2.0, CLAVICAL_LENGTH, THE_INSET), limits::CLAVICAL.mirrored()),
+    bone("Right Arm", Segment::UpperArm, 38, 37, BoneShape::hanging(4.20, 3.25, 5.075, 3.5, HUMERUS_LENGTH, 1.0), limits::SHOULDER.mirrored()),
+    bone("Right Forearm", Segment::Forearm, 39, 38, BoneShape::hanging(3.75, 2.75, 3.75, 2.75, FOREARM_LENGTH, 1.0), limits::ELBOW.mirrored()),
+    bone("Right Hand", Segment::Hand, 40, 39, BoneShape::hanging(3.0, 2.0, 4.0, 5.0, HAND_LENGTH, THE_INSET), limits::WRIST.mirrored()),
+];
+
+/// A single bone of `SKELETON`, with vertebra runs expanded.
+#[derive(Clone)]
+pub struct SkeletonBone {
+    pub joint_id: i32,
+    pub parent: Option<i32>,
+    pub segment: Segment,
+    pub cube: BoneCube,
+    pub rom: RangeOfMotion,
+}
 
-    name = "Left Clavical".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 2.0,
-            x_bottom: 2.0,
-            z_top: 2.0,
-            z_bottom: 2.0,
-            y: clavical_lengh,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -clavical_lengh / 2.0, 0.0),
-            name,
-        },
-    );
+impl BoneDef {
+    fn expand(&self) -> impl Iterator<Item = SkeletonBone> + '_ {
+        (0..self.count).map(move |i| {
+            let name = if self.count == 1 {
+                self.name.to_string()
+            } else {
+                format!("{} {}", self.name, self.count - i)
+            };
+            let shape = self.shape;
+            SkeletonBone {
+                joint_id: self.joint_id + i,
+                parent: if i == 0 { self.parent } else { Some(self.joint_id + i - 1) },
+                segment: self.segment,
+                cube: BoneCube {
+                    x_top: shape.x_top,
+                    x_bottom: shape.x_bottom,
+                    z_top: shape.z_top,
+                    z_bottom: shape.z_bottom,
+                    y: shape.length,
+                    inset: shape.inset,
+                    transform: Transform::from_xyz(0.0, shape.offset, 0.0),
+                    name,
+                },
+                rom: if i == 0 {
+                    self.rom
+                } else {
+                    RangeOfMotion {
+                        neutral: Vec3::ZERO,
+                        ..self.rom
+                    }
+                },
+            }
+        })
+    }
+}
 
-    name = "Right Clavical".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 2.0,
-            x_bottom: 2.0,
-            z_top: 2.0,
-            z_bottom: 2.0,
-            y: clavical_lengh,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -clavical_lengh / 2.0, 0.0),
-            name,
-        },
-    );
+static BONES: LazyLock<Vec<SkeletonBone>> =
+    LazyLock::new(|| SKELETON.iter().flat_map(BoneDef::expand).collect());
 
-    name = "Left Arm".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 4.20,
-            x_bottom: 3.25,
-            z_top: 5.075,
-            z_bottom: 3.5,
-            y: humerus_length,
-            inset: 1.0,
-            transform: Transform::from_xyz(0.0, -humerus_length / 2.0, 0.0),
-            name,
-        },
-    );
+/// Every bone of `SKELETON` in joint id order, parents first.
+pub fn bones() -> &'static [SkeletonBone] {
+    &BONES
+}
 
-    name = "Right Arm".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 4.20,
-            x_bottom: 3.25,
-            z_top: 5.075,
-            z_bottom: 3.5,
-            y: humerus_length,
-            inset: 1.0,
-            transform: Transform::from_xyz(0.0, -humerus_length / 2.0, 0.0),
-            name,
-        },
-    );
+pub fn find_bone(joint_id: i32) -> Option<&'static SkeletonBone> {
+    bones().iter().find(|bone| bone.joint_id == joint_id)
+}
 
-    name = "Left Forearm".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.75,
-            x_bottom: 2.75,
-            z_top: 3.75,
-            z_bottom: 2.75,
-            y: forearm_length,
-            inset: 1.0,
-            transform: Transform::from_xyz(0.0, -forearm_length / 2.0, 0.0),
-            name,
-        },
-    );
+pub fn find_bone_by_name(name: &str) -> Option<&'static SkeletonBone> {
+    bones().iter().find(|bone| bone.cube.name == name)
+}
 
-    name = "Right Forearm".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.75,
-            x_bottom: 2.75,
-            z_top: 3.75,
-            z_bottom: 2.75,
-            y: forearm_length,
-            inset: 1.0,
-            transform: Transform::from_xyz(0.0, -forearm_length / 2.0, 0.0),
-            name,
-        },
-    );
+pub fn parent(joint_id: i32) -> Option<i32> {
+    find_bone(joint_id).and_then(|bone| bone.parent)
+}
 
-    name = "Left Hand".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.0,
-            x_bottom: 2.0,
-            z_top: 4.0,
-            z_bottom: 5.0,
-            y: hand_length,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -hand_length / 2.0, 0.0),
-            name,
-        },
-    );
+pub fn children(joint_id: i32) -> Vec<i32> {
+    bones()
+        .iter()
+        .filter(|bone| bone.parent == Some(joint_id))
+        .map(|bone| bone.joint_id)
+        .collect()
+}
 
-    name = "Right Hand".to_string();
-    map.insert(
-        name.clone(),
-        BoneCube {
-            x_top: 3.0,
-            x_bottom: 2.0,
-            z_top: 4.0,
-            z_bottom: 5.0,
-            y: hand_length,
-            inset: the_inset,
-            transform: Transform::from_xyz(0.0, -hand_length / 2.0, 0.0),
-            name,
-        },
-    );
+/// The same bone on the other side of the body, `joint_id` itself for the
+/// bones down the middle. Under a bone down the middle the pair are the two
+/// children of the same segment, the thighs and the shoulders; further out
+/// a bone pairs with the child in its place under its parent's counterpart.
+pub fn opposite(joint_id: i32) -> i32 {
+    let Some(parent) = parent(joint_id) else {
+        return joint_id;
+    };
+    let siblings = children(parent);
+    let opposite_parent = opposite(parent);
+    if opposite_parent != parent {
+        let index = siblings.iter().position(|sibling| *sibling == joint_id);
+        return index
+            .and_then(|index| children(opposite_parent).get(index).copied())
+            .unwrap_or(joint_id);
+    }
+    let segment = find_bone(joint_id).map(|bone| bone.segment);
+    siblings
+        .into_iter()
+        .find(|sibling| *sibling != joint_id && find_bone(*sibling).map(|bone| bone.segment) == segment)
+        .unwrap_or(joint_id)
+}
 
-    map
+/// `joint_id` and the joints above it, up to the hips.
+pub fn ancestors(joint_id: i32) -> Vec<i32> {
+    let mut chain = vec![joint_id];
+    while let Some(parent) = parent(*chain.last().unwrap()) {
+        chain.push(parent);
+    }
+    chain
 }
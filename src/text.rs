@@ -0,0 +1,185 @@
+//! `AsanaData` as a directory of pretty-printed JSON, one file per asana,
+//! so pose changes can be reviewed in git.
+//!
+//! ```text
+//! poses/
+//!   categories.json
+//!   006-chaturanga-dandasana.json   the asana and its 40 joints
+//!   ...
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{skeleton::Joint, Asana, AsanaData, Category};
+
+pub const CATEGORIES_FILE: &str = "categories.json";
+
+#[derive(Debug)]
+pub enum TextError {
+    Io(PathBuf, std::io::Error),
+    Json(PathBuf, serde_json::Error),
+    /// A directory with files in it but no `CATEGORIES_FILE`, which isn't
+    /// written over.
+    NotATree(PathBuf),
+    /// Two asana files with the same pose id, which would take each
+    /// other's joints.
+    DuplicatePose { pose_id: i32, first: PathBuf, second: PathBuf },
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            TextError::Json(path, e) => write!(f, "{}: {e}", path.display()),
+            TextError::NotATree(path) => write!(
+                f,
+                "{} has files in it but no {CATEGORIES_FILE}, it isn't a pose tree",
+                path.display()
+            ),
+            TextError::DuplicatePose { pose_id, first, second } => write!(
+                f,
+                "{} and {} both have pose {pose_id}",
+                first.display(),
+                second.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+#[derive(Serialize)]
+struct AsanaFileRef<'a> {
+    #[serde(flatten)]
+    asana: &'a Asana,
+    joints: Vec<&'a Joint>,
+}
+
+#[derive(Deserialize)]
+struct AsanaFile {
+    #[serde(flatten)]
+    asana: Asana,
+    joints: Vec<Joint>,
+}
+
+/// `006-chaturanga-dandasana.json`
+pub fn file_name(asana: &Asana) -> String {
+    let slug = asana
+        .sanskrit
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    format!("{:03}-{slug}.json", asana.asana_id)
+}
+
+/// Whether `name` is named like a file `file_name` makes, digits, then a
+/// dash and a lowercase slug.
+fn is_asana_file(name: &str) -> bool {
+    let Some((id, slug)) = name.strip_suffix(".json").and_then(|stem| stem.split_once('-')) else {
+        return false;
+    };
+    id.len() >= 3
+        && id.chars().all(|c| c.is_ascii_digit())
+        && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Names of the files in `dir` that `write_tree` wrote, `CATEGORIES_FILE`
+/// left out.
+fn asana_files(dir: &Path) -> Result<Vec<PathBuf>, TextError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| TextError::Io(dir.to_path_buf(), e))? {
+        let path = entry.map_err(|e| TextError::Io(dir.to_path_buf(), e))?.path();
+        if path.file_name().and_then(|name| name.to_str()).is_some_and(is_asana_file) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+fn write_json(path: PathBuf, value: &impl Serialize) -> Result<(), TextError> {
+    let mut text = serde_json::to_string_pretty(value).map_err(|e| TextError::Json(path.clone(), e))?;
+    text.push('\n');
+    fs::write(&path, text).map_err(|e| TextError::Io(path, e))
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: PathBuf) -> Result<T, TextError> {
+    let text = fs::read_to_string(&path).map_err(|e| TextError::Io(path.clone(), e))?;
+    serde_json::from_str(&text).map_err(|e| TextError::Json(path, e))
+}
+
+/// Writes `data` into `dir`, creating it if needed. Asana files in `dir`
+/// that aren't written again are removed so deleted or renamed asanas don't
+/// linger. A directory that has other files but no `CATEGORIES_FILE` is
+/// refused rather than filled with asanas.
+pub fn write_tree(data: &AsanaData, dir: &Path) -> Result<(), TextError> {
+    if dir.is_dir() && !dir.join(CATEGORIES_FILE).is_file() {
+        let mut entries = fs::read_dir(dir).map_err(|e| TextError::Io(dir.to_path_buf(), e))?;
+        if entries.next().is_some() {
+            return Err(TextError::NotATree(dir.to_path_buf()));
+        }
+    }
+    fs::create_dir_all(dir).map_err(|e| TextError::Io(dir.to_path_buf(), e))?;
+    let mut written = vec![PathBuf::from(CATEGORIES_FILE)];
+    write_json(dir.join(CATEGORIES_FILE), &data.categories)?;
+
+    for asana in data.asanas.iter() {
+        let mut joints = data
+            .poses
+            .get(&asana.pose_id)
+            .map(|joints| joints.iter().collect::<Vec<&Joint>>())
+            .unwrap_or_default();
+        joints.sort_by_key(|joint| joint.joint_id);
+        let name = file_name(asana);
+        write_json(dir.join(&name), &AsanaFileRef { asana, joints })?;
+        written.push(PathBuf::from(name));
+    }
+
+    for path in asana_files(dir)? {
+        if !written.iter().any(|name| path.file_name() == Some(name.as_os_str())) {
+            fs::remove_file(&path).map_err(|e| TextError::Io(path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a directory written by `write_tree`, skipping files not named like
+/// its asana files. Asanas come back sorted by file name, and one without
+/// joints comes back without a pose, as `write_tree` wrote it.
+pub fn read_tree(dir: &Path) -> Result<AsanaData, TextError> {
+    let categories: Vec<Category> = read_json(dir.join(CATEGORIES_FILE))?;
+
+    let mut paths = asana_files(dir)?;
+    paths.sort();
+
+    let mut asanas = Vec::new();
+    let mut poses = HashMap::new();
+    let mut pose_files: HashMap<i32, PathBuf> = HashMap::new();
+    for path in paths {
+        let file: AsanaFile = read_json(path.clone())?;
+        let pose_id = file.asana.pose_id;
+        if let Some(first) = pose_files.insert(pose_id, path.clone()) {
+            return Err(TextError::DuplicatePose {
+                pose_id,
+                first,
+                second: path,
+            });
+        }
+        if !file.joints.is_empty() {
+            poses.insert(pose_id, file.joints);
+        }
+        asanas.push(file.asana);
+    }
+    Ok(AsanaData {
+        asanas,
+        poses,
+        categories,
+    })
+}
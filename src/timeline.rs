@@ -0,0 +1,171 @@
+//! Flows authored as keyframes: skeleton poses at points in time, with the
+//! in-between poses eased from one key to the next.
+//!
+//! Keys hold the joints of the default body, as the pose database does, so
+//! a pose captured from the viewer and an asana inserted from the database
+//! mix freely. A timeline is saved as a JSON file.
+
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use bevy::math::{
+    curve::{Curve, EaseFunction},
+    Mat4,
+};
+use bevy::transform::components::Transform;
+use serde::{Deserialize, Serialize};
+
+use crate::{skeleton::Joint, transition};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Seconds from the start of the timeline.
+    pub time: f32,
+    /// Curve the bones follow from the key before to this one.
+    pub easing: EaseFunction,
+    /// Where the pose came from, the asana's name for one from the database.
+    pub label: String,
+    pub joints: Vec<Joint>,
+}
+
+/// Another key is already at the time a key was moved to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeTaken(pub f32);
+
+impl fmt::Display for TimeTaken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "another key is at {} s", self.0)
+    }
+}
+
+impl std::error::Error for TimeTaken {}
+
+/// Why a timeline file couldn't be read or written.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub enum TimelineError {
+    Io(PathBuf, std::io::Error),
+    Json(PathBuf, serde_json::Error),
+    /// A key at a time that isn't a number of seconds.
+    BadKeyTime(PathBuf, f32),
+    /// Two keys at the same time.
+    DuplicateKeyTime(PathBuf, f32),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl fmt::Display for TimelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelineError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            TimelineError::Json(path, e) => write!(f, "{}: {e}", path.display()),
+            TimelineError::BadKeyTime(path, time) => write!(f, "{}: a key is at {time} s", path.display()),
+            TimelineError::DuplicateKeyTime(path, time) => {
+                write!(f, "{}: more than one key is at {time} s", path.display())
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::error::Error for TimelineError {}
+
+/// Keyframes in time order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Timeline {
+    /// Time of the last key.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Adds `keyframe` in time order, in place of a key at the same time.
+    /// Where it went.
+    pub fn insert(&mut self, keyframe: Keyframe) -> usize {
+        if let Some(index) = self.keyframes.iter().position(|key| key.time == keyframe.time) {
+            self.keyframes[index] = keyframe;
+            return index;
+        }
+        let index = self.keyframes.partition_point(|key| key.time < keyframe.time);
+        self.keyframes.insert(index, keyframe);
+        index
+    }
+
+    /// Moves the key at `index` to `time`, where it went. Fails, leaving
+    /// the key where it was, when another key is already at `time`.
+    pub fn retime(&mut self, index: usize, time: f32) -> Result<usize, TimeTaken> {
+        let time = time.max(0.0);
+        if self.keyframes.iter().enumerate().any(|(other, key)| other != index && key.time == time) {
+            return Err(TimeTaken(time));
+        }
+        let mut keyframe = self.keyframes.remove(index);
+        keyframe.time = time;
+        Ok(self.insert(keyframe))
+    }
+
+    /// Parent-relative matrices of the joints at `time`, eased between the
+    /// keys either side of it and held before the first and after the last.
+    /// `None` without keys.
+    pub fn sample(&self, time: f32) -> Option<Vec<(i32, Mat4)>> {
+        let after = self.keyframes.partition_point(|key| key.time <= time);
+        let Some(to) = self.keyframes.get(after).filter(|_| after > 0) else {
+            let key = self.keyframes.get(after.saturating_sub(1))?;
+            return Some(matrices(&key.joints));
+        };
+        let from = &self.keyframes[after - 1];
+        let t = (time - from.time) / (to.time - from.time);
+        let t = to.easing.sample_clamped(t);
+        let sampled = from
+            .joints
+            .iter()
+            .map(|joint| {
+                let start = Transform::from_matrix(joint.matrix());
+                let end = to
+                    .joints
+                    .iter()
+                    .find(|other| other.joint_id == joint.joint_id)
+                    .map_or(start, |other| Transform::from_matrix(other.matrix()));
+                (joint.joint_id, transition::interpolate(&start, &end, t).compute_matrix())
+            })
+            .collect();
+        Some(sampled)
+    }
+}
+
+fn matrices(joints: &[Joint]) -> Vec<(i32, Mat4)> {
+    joints.iter().map(|joint| (joint.joint_id, joint.matrix())).collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file {
+    use std::{fs, path::Path};
+
+    use super::{Timeline, TimelineError};
+
+    impl Timeline {
+        /// Reads the timeline at `path`, its keys sorted by time. Fails on a
+        /// time that isn't finite or that two keys share.
+        pub fn read(path: &Path) -> Result<Timeline, TimelineError> {
+            let json = fs::read_to_string(path).map_err(|e| TimelineError::Io(path.to_path_buf(), e))?;
+            let mut timeline: Timeline =
+                serde_json::from_str(&json).map_err(|e| TimelineError::Json(path.to_path_buf(), e))?;
+            if let Some(key) = timeline.keyframes.iter().find(|key| !key.time.is_finite()) {
+                return Err(TimelineError::BadKeyTime(path.to_path_buf(), key.time));
+            }
+            timeline.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+            if let Some(pair) = timeline.keyframes.windows(2).find(|pair| pair[0].time == pair[1].time) {
+                return Err(TimelineError::DuplicateKeyTime(path.to_path_buf(), pair[0].time));
+            }
+            Ok(timeline)
+        }
+
+        pub fn write(&self, path: &Path) -> Result<(), TimelineError> {
+            let json =
+                serde_json::to_string_pretty(self).map_err(|e| TimelineError::Json(path.to_path_buf(), e))?;
+            fs::write(path, json + "\n").map_err(|e| TimelineError::Io(path.to_path_buf(), e))
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Moving bones from one pose to the next over time instead of in one jump.
+//!
+//! Every bone turns along the shortest arc between its two rotations and
+//! its origin moves in a straight line, both paced by an easing curve. A
+//! transition only remembers where each bone started, so one interrupted
+//! by another simply starts the next from wherever the bones had got to.
+
+use bevy::{
+    ecs::entity::Entity,
+    math::curve::{Curve, EaseFunction},
+    transform::components::Transform,
+};
+
+/// Easing curves offered for transitions.
+pub const EASINGS: [EaseFunction; 4] = [
+    EaseFunction::Linear,
+    EaseFunction::SmoothStep,
+    EaseFunction::CubicInOut,
+    EaseFunction::SineInOut,
+];
+
+/// `from` moved a fraction `t` of the way to `to`.
+pub fn interpolate(from: &Transform, to: &Transform, t: f32) -> Transform {
+    Transform {
+        translation: from.translation.lerp(to.translation, t),
+        rotation: from.rotation.slerp(to.rotation, t),
+        scale: from.scale.lerp(to.scale, t),
+    }
+}
+
+/// Bones on their way to a pose.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// Each bone with where it started and where it ends up.
+    bones: Vec<(Entity, Transform, Transform)>,
+    duration: f32,
+    easing: EaseFunction,
+    elapsed: f32,
+}
+
+impl Transition {
+    /// Moves `bones`, given as (bone, where it is, where it's going), over
+    /// `duration` seconds.
+    pub fn new(bones: Vec<(Entity, Transform, Transform)>, duration: f32, easing: EaseFunction) -> Transition {
+        Transition {
+            bones,
+            duration,
+            easing,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Fraction of the duration gone by, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// Replaces where each bone started and where it's going with `place`
+    /// of them, as when the bones are moved onto another body midway.
+    pub fn replace(&mut self, mut place: impl FnMut(Entity, &Transform) -> Transform) {
+        for (entity, from, to) in self.bones.iter_mut() {
+            *from = place(*entity, from);
+            *to = place(*entity, to);
+        }
+    }
+
+    /// Moves the transition on by `seconds` and returns where every bone is
+    /// now. Bones land exactly on their targets once it's finished.
+    pub fn advance(&mut self, seconds: f32) -> Vec<(Entity, Transform)> {
+        self.elapsed += seconds;
+        let t = self.easing.sample_clamped(self.progress());
+        self.bones
+            .iter()
+            .map(|(entity, from, to)| {
+                let placed = if self.is_finished() { *to } else { interpolate(from, to, t) };
+                (*entity, placed)
+            })
+            .collect()
+    }
+}
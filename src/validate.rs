@@ -0,0 +1,280 @@
+//! Checks that `AsanaData` is something `set_pose` can show, and repairs
+//! what can be repaired.
+
+use std::{collections::BTreeSet, fmt, mem};
+
+use bevy::math::Vec3;
+
+use crate::{pose, skeleton::Joint, AsanaData};
+
+/// Joint ids `spawn_skeleton` gives the bones.
+pub const JOINT_IDS: std::ops::RangeInclusive<i32> = 1..=40;
+
+/// Largest dot product between `up` and `forward` that is still accepted.
+pub const TOLERANCE: f32 = 1e-3;
+
+/// Largest drift of `up` or `forward` from unit length that is still
+/// accepted. The legacy editor saved axes up to 5% short, and since
+/// `Joint::matrix` keeps their length the poses are drawn that way.
+pub const LENGTH_TOLERANCE: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    Up,
+    Forward,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    DuplicateAsana,
+    UnknownCategory(i32),
+    MissingPose(i32),
+    MissingJoint,
+    UnexpectedJoint,
+    DuplicateJoint,
+    /// The joint is filed under the asana's pose but names another one.
+    WrongPoseId(i32),
+    NotFinite,
+    NotUnitLength { axis: Axis, length: f32 },
+    NotOrthogonal { dot: f32 },
+    /// The stored Euler angles describe another rotation than the axes.
+    AnglesDisagree([f32; 3]),
+}
+
+impl Problem {
+    /// Whether `repair` fixes it.
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Problem::DuplicateAsana)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::DuplicateAsana => write!(f, "asana is listed twice"),
+            Problem::UnknownCategory(category_id) => write!(f, "unknown category {category_id}"),
+            Problem::MissingPose(pose_id) => write!(f, "pose {pose_id} is missing"),
+            Problem::MissingJoint => write!(f, "missing"),
+            Problem::UnexpectedJoint => write!(f, "not a joint of the skeleton"),
+            Problem::DuplicateJoint => write!(f, "listed twice"),
+            Problem::WrongPoseId(pose_id) => write!(f, "belongs to pose {pose_id}"),
+            Problem::NotFinite => write!(f, "has a NaN or infinite value"),
+            Problem::NotUnitLength { axis, length } => {
+                write!(f, "{axis:?} has length {length}")
+            }
+            Problem::NotOrthogonal { dot } => write!(f, "up and forward have dot product {dot}"),
+            Problem::AnglesDisagree([x, y, z]) => {
+                write!(f, "angles {x}° {y}° {z}° don't match the axes")
+            }
+        }
+    }
+}
+
+/// One problem, located by asana and, for joint problems, joint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub asana_id: i32,
+    pub sanskrit: String,
+    pub joint_id: Option<i32>,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.sanskrit.trim(), self.asana_id)?;
+        if let Some(joint_id) = self.joint_id {
+            write!(f, " joint {joint_id}")?;
+        }
+        write!(f, ": {}", self.problem)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub asanas_checked: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+fn up(joint: &Joint) -> Vec3 {
+    Vec3::new(joint.up_x, joint.up_y, joint.up_z)
+}
+
+fn forward(joint: &Joint) -> Vec3 {
+    Vec3::new(joint.forward_x, joint.forward_y, joint.forward_z)
+}
+
+fn origin(joint: &Joint) -> Vec3 {
+    Vec3::new(joint.origin_x, joint.origin_y, joint.origin_z)
+}
+
+fn joint_problems(joint: &Joint) -> Vec<Problem> {
+    if !(up(joint).is_finite() && forward(joint).is_finite() && origin(joint).is_finite()) {
+        return vec![Problem::NotFinite];
+    }
+    let mut problems = Vec::new();
+    for (axis, vector) in [(Axis::Up, up(joint)), (Axis::Forward, forward(joint))] {
+        let length = vector.length();
+        if (length - 1.0).abs() > LENGTH_TOLERANCE {
+            problems.push(Problem::NotUnitLength { axis, length });
+        }
+    }
+    let dot = up(joint).dot(forward(joint));
+    if dot.abs() > TOLERANCE {
+        problems.push(Problem::NotOrthogonal { dot });
+    }
+    if let Some(angles) = joint.angles {
+        if !joint.angles_match(angles.into()) {
+            problems.push(Problem::AnglesDisagree(angles));
+        }
+    }
+    problems
+}
+
+pub fn validate(data: &AsanaData) -> Report {
+    let mut report = Report {
+        asanas_checked: data.asanas.len(),
+        issues: Vec::new(),
+    };
+    let mut seen = BTreeSet::new();
+    for asana in data.asanas.iter() {
+        let mut push = |joint_id: Option<i32>, problem: Problem| {
+            report.issues.push(Issue {
+                asana_id: asana.asana_id,
+                sanskrit: asana.sanskrit.clone(),
+                joint_id,
+                problem,
+            })
+        };
+        if !seen.insert(asana.asana_id) {
+            push(None, Problem::DuplicateAsana);
+        }
+        for category_id in asana.category_ids.iter() {
+            if !data
+                .categories
+                .iter()
+                .any(|category| category.category_id == *category_id)
+            {
+                push(None, Problem::UnknownCategory(*category_id));
+            }
+        }
+        let Some(joints) = data.poses.get(&asana.pose_id) else {
+            push(None, Problem::MissingPose(asana.pose_id));
+            continue;
+        };
+
+        let mut joint_ids = BTreeSet::new();
+        for joint in joints.iter() {
+            let joint_id = Some(joint.joint_id);
+            if !JOINT_IDS.contains(&joint.joint_id) {
+                push(joint_id, Problem::UnexpectedJoint);
+                continue;
+            }
+            if !joint_ids.insert(joint.joint_id) {
+                push(joint_id, Problem::DuplicateJoint);
+                continue;
+            }
+            if joint.pose_id != asana.pose_id {
+                push(joint_id, Problem::WrongPoseId(joint.pose_id));
+            }
+            for problem in joint_problems(joint) {
+                push(joint_id, problem);
+            }
+        }
+        for joint_id in JOINT_IDS.filter(|joint_id| !joint_ids.contains(joint_id)) {
+            push(Some(joint_id), Problem::MissingJoint);
+        }
+    }
+    report
+}
+
+/// Re-orthonormalizes `up` and `forward`, keeping the direction of `up`, and
+/// drops stored angles that don't match them. `None` when either axis is degenerate.
+fn orthonormalize(joint: &Joint) -> Option<Joint> {
+    let up = up(joint).try_normalize()?;
+    let forward = forward(joint).reject_from_normalized(up).try_normalize()?;
+    Some(Joint {
+        up_x: up.x,
+        up_y: up.y,
+        up_z: up.z,
+        forward_x: forward.x,
+        forward_y: forward.y,
+        forward_z: forward.z,
+        angles: joint
+            .angles
+            .filter(|angles| joint.angles_match(Vec3::from(*angles))),
+        ..joint.clone()
+    })
+}
+
+/// Fixes every repairable problem `validate` finds and returns those it
+/// fixed. Drifted axes are re-orthonormalized; missing joints, missing poses
+/// and joints that can't be salvaged are copied from `pose::TADASANA`, so
+/// they need its pose to be intact.
+pub fn repair(data: &mut AsanaData) -> Vec<Issue> {
+    let found = validate(data).issues;
+    let reference = pose::find_asana(data, pose::TADASANA)
+        .ok()
+        .and_then(|asana| data.poses.get(&asana.pose_id))
+        .cloned()
+        .unwrap_or_default();
+    let from_reference = |joint_id: i32, pose_id: i32| {
+        reference
+            .iter()
+            .find(|joint| joint.joint_id == joint_id)
+            .filter(|joint| joint_problems(joint).is_empty())
+            .map(|joint| Joint {
+                pose_id,
+                ..joint.clone()
+            })
+    };
+
+    let category_ids = data
+        .categories
+        .iter()
+        .map(|category| category.category_id)
+        .collect::<BTreeSet<i32>>();
+    for asana in data.asanas.iter_mut() {
+        asana.category_ids.retain(|category_id| category_ids.contains(category_id));
+
+        let joints = data.poses.get(&asana.pose_id).cloned().unwrap_or_default();
+        let mut repaired = Vec::new();
+        for joint_id in JOINT_IDS {
+            let joint = joints
+                .iter()
+                .find(|joint| joint.joint_id == joint_id)
+                .map(|joint| Joint {
+                    pose_id: asana.pose_id,
+                    ..joint.clone()
+                });
+            let joint = match joint {
+                Some(joint) if joint_problems(&joint).is_empty() => Some(joint),
+                Some(joint) => orthonormalize(&joint)
+                    .filter(|joint| joint_problems(joint).is_empty())
+                    .or_else(|| from_reference(joint_id, asana.pose_id)),
+                None => from_reference(joint_id, asana.pose_id),
+            };
+            repaired.extend(joint);
+        }
+        if !repaired.is_empty() {
+            data.poses.insert(asana.pose_id, repaired);
+        }
+    }
+
+    let remaining = validate(data).issues;
+    found
+        .into_iter()
+        .filter(|issue| {
+            !remaining.iter().any(|left| {
+                left.asana_id == issue.asana_id
+                    && left.joint_id == issue.joint_id
+                    && mem::discriminant(&left.problem) == mem::discriminant(&issue.problem)
+            })
+        })
+        .collect()
+}
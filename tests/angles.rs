@@ -0,0 +1,132 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::path::PathBuf;
+
+use bevy::math::{Mat4, Quat, Vec3};
+use common::{asana, rest_pose};
+use rusqlite::Connection;
+use yogamat_wasm::{
+    db,
+    skeleton::{euler_degrees, rotation_from_degrees, Joint},
+    AsanaData,
+};
+
+/// Angles in the ranges `euler_degrees` gives, y within ±90°, and away
+/// from the gimbal lock at its ends where x and z trade off.
+const ANGLES: [[f32; 3]; 5] = [
+    [0.0, 0.0, 0.0],
+    [30.0, -45.0, 60.0],
+    [-120.0, 10.0, 170.0],
+    [90.0, 80.0, -90.0],
+    [175.0, -60.0, -179.5],
+];
+
+fn scratch_db(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("yogamat-{name}-{}.sql", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// One asana whose joint 5 is turned to `angles`.
+fn turned(angles: Vec3) -> AsanaData {
+    let mut joints = rest_pose(10);
+    joints[4].set_euler_degrees(angles);
+    AsanaData {
+        asanas: vec![asana(1, 10, "Tadasana")],
+        poses: [(10, joints)].into(),
+        categories: Vec::new(),
+    }
+}
+
+fn legacy_angles(path: &PathBuf, joint_id: i32) -> Vec3 {
+    let db = Connection::open(path).unwrap();
+    let (x, y, z) = db
+        .query_row(
+            "SELECT xAngle, yAngle, zAngle FROM joint WHERE jointID = ? AND poseID = 10",
+            [joint_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    Vec3::new(x, y, z)
+}
+
+#[test]
+fn euler_angles_round_trip() {
+    for angles in ANGLES.map(Vec3::from) {
+        let back = euler_degrees(rotation_from_degrees(angles));
+        assert!(back.abs_diff_eq(angles, 1e-3), "{angles} came back as {back}");
+    }
+}
+
+#[test]
+fn angles_turn_the_axes() {
+    let mut joint = Joint::from_matrix(5, 10, &Mat4::IDENTITY);
+    joint.set_euler_degrees(Vec3::new(0.0, 0.0, 90.0));
+    let up = Vec3::new(joint.up_x, joint.up_y, joint.up_z);
+    let forward = Vec3::new(joint.forward_x, joint.forward_y, joint.forward_z);
+    assert!(up.abs_diff_eq(Vec3::NEG_X, 1e-6));
+    assert!(forward.abs_diff_eq(Vec3::Z, 1e-6));
+
+    // Computed from the axes once the stored angles are gone.
+    joint.angles = None;
+    assert!(joint.euler_degrees().abs_diff_eq(Vec3::new(0.0, 0.0, 90.0), 1e-3));
+}
+
+#[test]
+fn angles_match_within_a_degree() {
+    for angles in ANGLES.map(Vec3::from) {
+        let mut joint = Joint::from_matrix(5, 10, &Mat4::IDENTITY);
+        joint.set_euler_degrees(angles);
+        assert!(joint.angles_match(angles));
+        assert!(joint.angles_match(angles + Vec3::new(0.5, 0.0, 0.0)));
+        assert!(!joint.angles_match(angles + Vec3::new(0.0, 0.0, 5.0)));
+    }
+    let joint = Joint::from_matrix(5, 10, &Mat4::from_quat(Quat::from_rotation_y(1.0)));
+    assert!(!joint.angles_match(Vec3::ZERO));
+}
+
+#[test]
+fn legacy_y_angle_is_180_at_rest() {
+    let path = scratch_db("legacy-rest");
+    db::write_asana_data(&path, &turned(Vec3::ZERO)).unwrap();
+    let rest = legacy_angles(&path, 6);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(rest, Vec3::new(0.0, 180.0, 0.0));
+}
+
+#[test]
+fn legacy_angles_round_trip() {
+    for angles in ANGLES.map(Vec3::from) {
+        let path = scratch_db("legacy-round-trip");
+        db::write_asana_data(&path, &turned(angles)).unwrap();
+        let legacy = legacy_angles(&path, 5);
+        let read = db::read_asana_data(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // y is written half a turn round, kept within (-180°, 180°].
+        let offset = (legacy.y - angles.y).rem_euclid(360.0);
+        assert!((offset - 180.0).abs() < 1e-3, "{angles} was written as {legacy}");
+        assert!(legacy.y > -180.0 && legacy.y <= 180.0);
+        assert_eq!((legacy.x, legacy.z), (angles.x, angles.z));
+        let joint = &read.poses[&10][4];
+        assert!(Vec3::from(joint.angles.unwrap()).abs_diff_eq(angles, 1e-3));
+    }
+}
+
+#[test]
+fn legacy_angles_that_disagree_are_dropped() {
+    let path = scratch_db("legacy-disagree");
+    db::write_asana_data(&path, &turned(Vec3::new(30.0, 0.0, 0.0))).unwrap();
+    let db = Connection::open(&path).unwrap();
+    db.execute("UPDATE joint SET yAngle = 0 WHERE jointID = 5", []).unwrap();
+    drop(db);
+    let read = db::read_asana_data(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let joint = &read.poses[&10][4];
+    assert_eq!(joint.angles, None);
+    assert!(joint.euler_degrees().abs_diff_eq(Vec3::new(30.0, 0.0, 0.0), 1e-3));
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{joints, out_db};
+use yogamat_wasm::{
+    balance::{self, bone_masses, convex_hull, margin},
+    grounding,
+    kinematics::forward_kinematics,
+    proportions::BodyProportions,
+    skeleton,
+};
+
+#[test]
+fn hull_of_a_square_is_its_corners() {
+    let corners = [Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0), Vec2::new(4.0, 4.0), Vec2::new(0.0, 4.0)];
+    let mut points = corners.to_vec();
+    points.extend([Vec2::new(2.0, 2.0), Vec2::new(1.0, 3.0), Vec2::new(4.0, 2.0), corners[0]]);
+    let hull = convex_hull(points);
+    assert_eq!(hull.len(), 4);
+    assert!(corners.iter().all(|corner| hull.contains(corner)));
+    // Counter-clockwise from above is clockwise in (x, z).
+    let area = (0..hull.len())
+        .map(|i| hull[i].perp_dot(hull[(i + 1) % hull.len()]))
+        .sum::<f32>();
+    assert_eq!(area, -32.0);
+
+    assert_eq!(margin(Vec2::new(2.0, 2.0), &hull), Some(2.0));
+    assert_eq!(margin(Vec2::new(1.0, 2.0), &hull), Some(1.0));
+    assert_eq!(margin(Vec2::new(7.0, 2.0), &hull), Some(-3.0));
+    assert_eq!(margin(Vec2::new(7.0, 8.0), &hull), Some(-5.0));
+}
+
+#[test]
+fn collinear_contacts_have_no_inside() {
+    let hull = convex_hull(vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(3.0, 3.0)]);
+    assert_eq!(hull.len(), 2);
+    assert!(margin(Vec2::new(1.0, 1.0), &hull).unwrap() <= 0.0);
+    assert!(margin(Vec2::new(2.0, 0.0), &hull).unwrap() < 0.0);
+    assert_eq!(margin(Vec2::ZERO, &[]), None);
+}
+
+#[test]
+fn masses_add_up_to_one() {
+    for (name, body) in BodyProportions::PRESETS {
+        let masses = bone_masses(&body.bones());
+        assert!((masses.iter().sum::<f32>() - 1.0).abs() < 1e-5, "{name}");
+        assert!(masses.iter().all(|mass| *mass > 0.0), "{name}");
+    }
+    // The two sides weigh the same.
+    let bones = skeleton::bones();
+    let masses = bone_masses(bones);
+    for (bone, mass) in bones.iter().zip(masses.iter()) {
+        let opposite = bones.iter().position(|other| other.joint_id == skeleton::opposite(bone.joint_id)).unwrap();
+        assert_eq!(*mass, masses[opposite]);
+    }
+}
+
+#[test]
+fn standing_balances_and_leaning_over_does_not() {
+    let data = out_db();
+    let bones = skeleton::bones();
+    let posed = forward_kinematics(bones, Mat4::IDENTITY, joints(&data, "Tadasana")).unwrap();
+    let root = grounding::ground(bones, &posed, Transform::IDENTITY, None);
+    let standing = balance::balance(bones, &posed, root);
+    assert!(standing.margin.unwrap() > 0.0);
+    assert!((standing.floor - grounding::mat_top()).abs() < 1e-3);
+    assert!(standing.support.iter().all(|corner| corner.y == standing.floor));
+
+    // Tipped over by 17°, only an edge of the feet stays on the mat.
+    let tipped = Transform::from_rotation(Quat::from_rotation_x(0.3));
+    let root = grounding::ground(bones, &posed, tipped, None);
+    let leaning = balance::balance(bones, &posed, root);
+    assert!(leaning.margin.unwrap() < 0.0);
+}
+
+#[test]
+fn ungrounded_poses_stand_on_their_lowest_point() {
+    let data = out_db();
+    let bones = skeleton::bones();
+    let posed = forward_kinematics(bones, Mat4::IDENTITY, joints(&data, "Tadasana")).unwrap();
+    let grounded = balance::balance(bones, &posed, grounding::ground(bones, &posed, Transform::IDENTITY, None));
+    let floating = balance::balance(bones, &posed, Transform::from_xyz(0.0, 40.0, 0.0));
+    assert!(floating.floor > grounding::mat_top());
+    assert!((floating.margin.unwrap() - grounded.margin.unwrap()).abs() < 1e-3);
+    assert_eq!(floating.support.len(), grounded.support.len());
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use bevy::{
+    prelude::*,
+    render::mesh::{MeshVertexAttribute, VertexAttributeValues},
+};
+use common::out_db;
+use yogamat_wasm::{body_mesh::make_body_mesh, kinematics::pose_skeleton, proportions::BodyProportions};
+
+fn attribute(mesh: &Mesh, attribute: MeshVertexAttribute) -> &VertexAttributeValues {
+    mesh.attribute(attribute).unwrap()
+}
+
+#[test]
+fn every_pose_skins_cleanly() {
+    let data = out_db();
+    let bones = BodyProportions::default().bones();
+    for asana in data.asanas.iter() {
+        let pose = data.poses[&asana.pose_id]
+            .iter()
+            .map(|joint| (joint.joint_id, joint.matrix()))
+            .collect::<Vec<(i32, Mat4)>>();
+        let body = make_body_mesh(&bones, &pose).unwrap();
+        let name = &asana.sanskrit;
+
+        let VertexAttributeValues::Float32x4(weights) = attribute(&body.mesh, Mesh::ATTRIBUTE_JOINT_WEIGHT) else {
+            panic!("{name}: joint weights aren't Float32x4");
+        };
+        for weight in weights {
+            assert!(weight.iter().all(|w| *w >= 0.0), "{name}: {weight:?}");
+            assert!((weight.iter().sum::<f32>() - 1.0).abs() < 1e-5, "{name}: {weight:?}");
+        }
+
+        let VertexAttributeValues::Uint16x4(joints) = attribute(&body.mesh, Mesh::ATTRIBUTE_JOINT_INDEX) else {
+            panic!("{name}: joint indices aren't Uint16x4");
+        };
+        assert_eq!(joints.len(), weights.len());
+        for joint in joints {
+            assert!(joint.iter().all(|index| (*index as usize) < body.joint_ids.len()), "{name}: {joint:?}");
+        }
+
+        let posed = pose_skeleton(&bones, Mat4::IDENTITY, &pose).unwrap();
+        assert_eq!(body.inverse_bindposes.len(), body.joint_ids.len());
+        for (joint_id, inverse_bind) in body.joint_ids.iter().zip(body.inverse_bindposes.iter()) {
+            let bind = posed.global(*joint_id).unwrap();
+            assert!(
+                (*inverse_bind * bind).abs_diff_eq(Mat4::IDENTITY, 1e-3),
+                "{name}: joint {joint_id}: {}",
+                *inverse_bind * bind
+            );
+        }
+    }
+}
@@ -0,0 +1,155 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use common::{out_db, two_asanas};
+use yogamat_wasm::{collision, format, limits, validate, AsanaData};
+
+const OUT_DB: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/out_db");
+
+/// Runs the database tool from the crate root, where its default paths
+/// point, and returns its exit code and what it printed.
+fn database(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_database"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yogamat-cli-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_out_db(path: &Path, data: &AsanaData) {
+    fs::write(path, format::encode(data).unwrap()).unwrap();
+}
+
+fn arg(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+fn code_for(clean: bool) -> i32 {
+    if clean {
+        0
+    } else {
+        1
+    }
+}
+
+#[test]
+fn export_and_import() {
+    let dir = scratch_dir("export");
+    let json = dir.join("poses.json");
+    let sqlite = dir.join("poses.sql");
+    let tree = dir.join("tree");
+    let exported = database(&["export", "-i", "old_yogamatdb.sql", "-o", arg(&json)]);
+    let imported = database(&["import", "-i", arg(&json), "-o", arg(&sqlite)]);
+    let to_tree = database(&["export", "--input", arg(&sqlite), "--output", arg(&tree), "--to", "tree"]);
+    let from_json: AsanaData = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+    let tree_files = fs::read_dir(&tree).unwrap().count();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(exported.0, 0, "{}", exported.2);
+    assert!(exported.1.contains("asanas written to"), "{}", exported.1);
+    assert_eq!(imported.0, 0, "{}", imported.2);
+    assert_eq!(to_tree.0, 0, "{}", to_tree.2);
+    assert_eq!(tree_files, from_json.asanas.len() + 1);
+}
+
+#[test]
+fn checks_exit_with_what_they_found() {
+    let dir = scratch_dir("checks");
+    let path = dir.join("out_db");
+    let data = two_asanas();
+    write_out_db(&path, &data);
+    let validated = database(&["validate", arg(&path)]);
+    let limited = database(&["limits", arg(&path)]);
+    let collided = database(&["collisions", arg(&path), "--from", "bincode"]);
+    let stats = database(&["stats", arg(&path)]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(validated.0, code_for(validate::validate(&data).is_clean()), "{}", validated.2);
+    assert_eq!(limited.0, code_for(limits::check(&data).is_empty()), "{}", limited.2);
+    assert_eq!(collided.0, code_for(collision::check(&data).is_empty()), "{}", collided.2);
+    assert_eq!(stats.0, 0, "{}", stats.2);
+}
+
+#[test]
+fn repair_writes_to_output() {
+    let dir = scratch_dir("repair");
+    let path = dir.join("out_db");
+    let repaired = dir.join("repaired.json");
+    let mut data = two_asanas();
+    data.poses.get_mut(&20).unwrap().retain(|joint| joint.joint_id != 33);
+    write_out_db(&path, &data);
+    let broken = database(&["validate", arg(&path)]);
+    let repair = database(&["validate", arg(&path), "--repair", "-o", arg(&repaired)]);
+    let written = repaired.is_file();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(broken.0, 1);
+    assert_eq!(repair.0, 0, "{}", repair.2);
+    assert!(written);
+}
+
+#[test]
+fn mirror_gltf_and_diff() {
+    let dir = scratch_dir("mirror");
+    let mirrored = dir.join("mirrored");
+    let glb = dir.join("surya.glb");
+    let mirror = database(&["mirror", OUT_DB, "--asana", "Utthita Trikonasana", "-o", arg(&mirrored)]);
+    let gltf = database(&["gltf", OUT_DB, "-s", "Surya Namaskar B", "--breaths-per-minute", "12", "-o", arg(&glb)]);
+    let same = database(&["diff", OUT_DB, OUT_DB]);
+    let changed = database(&["diff", OUT_DB, arg(&mirrored), "--tolerance", "0.001"]);
+    let as_json = database(&["diff", OUT_DB, arg(&mirrored), "--new-from", "json"]);
+    let as_bincode = database(&["diff", OUT_DB, arg(&mirrored), "--old-from", "bincode", "--new-from", "bincode"]);
+    let mirrored_data = format::decode(&fs::read(&mirrored).unwrap()).unwrap();
+    let glb_written = glb.is_file();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(mirror.0, 0, "{}", mirror.2);
+    assert_eq!(mirrored_data.asanas.len(), out_db().asanas.len() + 1);
+    assert_eq!(gltf.0, 0, "{}", gltf.2);
+    assert!(glb_written);
+    assert_eq!(same.0, 0, "{}", same.2);
+    assert_eq!(changed.0, 1, "{}", changed.2);
+    // The mirrored file is bincode whatever --new-from says.
+    assert_eq!(as_json.0, 2);
+    assert_eq!(as_bincode.0, 1, "{}", as_bincode.2);
+}
+
+#[test]
+fn bad_arguments_exit_with_2() {
+    for args in [
+        &["pose"][..],
+        &["validate", "--output", "repaired"],
+        &["export", "--to", "yaml"],
+        &["gltf", "--breaths-per-minute", "0"],
+        &["diff", "out_db"],
+        &["diff", "out_db", "out_db", "--old-from", "yaml"],
+    ] {
+        let (code, _stdout, stderr) = database(args);
+        assert_eq!(code, 2, "{args:?}: {stderr}");
+    }
+    let (code, _stdout, stderr) = database(&["diff", "out_db", "out_db", "--tolerance=-0.1"]);
+    assert_eq!(code, 2);
+    assert!(stderr.contains("must be a number from 0 up"), "{stderr}");
+    let (code, _stdout, stderr) = database(&["stats", "no-such-file"]);
+    assert_eq!(code, 2);
+    assert!(stderr.contains("no-such-file"), "{stderr}");
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use bevy::math::{Mat4, Quat};
+use common::{joints, out_db};
+use yogamat_wasm::{collision, skeleton::Joint};
+
+const LEFT_FEMUR: i32 = 2;
+const LEFT_CALF: i32 = 3;
+const LEFT_FOOT: i32 = 4;
+
+/// `pose` with `joint_id` turned further by `turn` in its parent's frame.
+fn turned(pose: &[Joint], joint_id: i32, turn: Quat) -> Vec<Joint> {
+    pose.iter()
+        .map(|joint| {
+            if joint.joint_id != joint_id {
+                return joint.clone();
+            }
+            let mat = joint.matrix();
+            let rotated = Mat4::from_quat(turn) * mat;
+            Joint::from_matrix(joint_id, joint.pose_id, &Mat4 { w_axis: mat.w_axis, ..rotated })
+        })
+        .collect()
+}
+
+#[test]
+fn holding_a_foot_passes_through_nothing() {
+    let data = out_db();
+    for name in [
+        "Tadasana",
+        "Padangusthasana",
+        "Paschimottanasana A",
+        "Baddha Konasana A",
+        "Supta Padangusthasana A",
+        "Ananda Balasana",
+        "Sirsasana A",
+    ] {
+        assert_eq!(collision::pose_collisions(joints(&data, name)).unwrap(), Vec::new(), "{name}");
+    }
+}
+
+#[test]
+fn a_knee_bent_backwards_puts_the_foot_through_the_thigh() {
+    let data = out_db();
+    let pose = turned(joints(&data, "Tadasana"), LEFT_CALF, Quat::from_rotation_x(-170f32.to_radians()));
+    let found = collision::pose_collisions(&pose).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].joints, (LEFT_FEMUR, LEFT_FOOT));
+    // Deeper than the foot is thick.
+    assert!(found[0].depth > 3.5);
+}
@@ -0,0 +1,53 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use bevy::math::Mat4;
+use yogamat_wasm::{format, pose::TADASANA, skeleton::Joint, validate::JOINT_IDS, Asana, AsanaData};
+
+/// The pose database the app ships with.
+pub fn out_db() -> AsanaData {
+    format::decode(include_bytes!("../../out_db")).unwrap()
+}
+
+/// Joints of the pose of the asana named `sanskrit`, ignoring the stray
+/// whitespace some names have in the database.
+pub fn joints<'a>(data: &'a AsanaData, sanskrit: &str) -> &'a [Joint] {
+    let asana = data
+        .asanas
+        .iter()
+        .find(|asana| asana.sanskrit.trim() == sanskrit)
+        .unwrap_or_else(|| panic!("no asana is named {sanskrit}"));
+    &data.poses[&asana.pose_id]
+}
+
+pub fn asana(asana_id: i32, pose_id: i32, sanskrit: &str) -> Asana {
+    Asana {
+        asana_id,
+        pose_id,
+        sanskrit: sanskrit.to_string(),
+        english: String::new(),
+        unicode: None,
+        notes: None,
+        category_ids: Vec::new(),
+        dristi: None,
+    }
+}
+
+/// Every joint of the skeleton at rest, all of them identity matrices.
+pub fn rest_pose(pose_id: i32) -> Vec<Joint> {
+    JOINT_IDS
+        .map(|joint_id| Joint::from_matrix(joint_id, pose_id, &Mat4::IDENTITY))
+        .collect()
+}
+
+/// Tadasana with all its joints and Virasana, whose pose is broken by each
+/// test, with their ids in out_db.
+pub fn two_asanas() -> AsanaData {
+    AsanaData {
+        asanas: vec![asana(TADASANA, 10, "Tadasana"), asana(170, 20, "Virasana")],
+        poses: HashMap::from([(10, rest_pose(10)), (20, rest_pose(20))]),
+        categories: Vec::new(),
+    }
+}
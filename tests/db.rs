@@ -0,0 +1,98 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use bevy::math::{Mat4, Vec3};
+use common::two_asanas;
+use yogamat_wasm::{
+    db::{self, DbError},
+    skeleton::Joint,
+    AsanaData, Category,
+};
+
+fn scratch_db(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("yogamat-{name}-{}.sql", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn updates_a_pose() {
+    let path = scratch_db("update");
+    db::write_asana_data(&path, &two_asanas()).unwrap();
+    let moved = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0));
+    let joints = vec![Joint::from_matrix(4, 20, &moved)];
+    db::update_pose(&path, &joints).unwrap();
+    let data = db::read_asana_data(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let joint = data.poses[&20].iter().find(|joint| joint.joint_id == 4).unwrap();
+    assert_eq!(joint.matrix(), moved);
+    assert!(data.poses[&10].iter().all(|joint| joint.matrix() == Mat4::IDENTITY));
+}
+
+#[test]
+fn pose_it_doesnt_have_changes_nothing() {
+    let path = scratch_db("missing-pose");
+    db::write_asana_data(&path, &two_asanas()).unwrap();
+    // Pose 20's joints come first, so they would be written before the
+    // missing one is found.
+    let moved = Mat4::from_translation(Vec3::X);
+    let joints = [Joint::from_matrix(4, 20, &moved), Joint::from_matrix(1, 30, &moved)];
+    let error = db::update_pose(&path, &joints).unwrap_err();
+    let data = db::read_asana_data(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(matches!(error, DbError::JointNotFound { pose_id: 30, joint_id: 1 }));
+    assert_eq!(error.to_string(), "pose 30 has no joint 1 in the database");
+    assert!(data.poses[&20].iter().all(|joint| joint.matrix() == Mat4::IDENTITY));
+}
+
+fn ids(data: &AsanaData, sanskrit: &str) -> Vec<i32> {
+    let asana = data.asanas.iter().find(|asana| asana.sanskrit == sanskrit).unwrap();
+    asana.category_ids.clone()
+}
+
+#[test]
+fn reads_categories() {
+    let data = db::read_asana_data(concat!(env!("CARGO_MANIFEST_DIR"), "/old_yogamatdb.sql")).unwrap();
+    assert_eq!(data.categories.len(), 13);
+    assert_eq!(data.categories[0].name, "Seated");
+    assert_eq!(data.categories[2].name, "Standing");
+    // Sorted by id, whatever order categoryList has them in.
+    assert_eq!(ids(&data, "Ardha Matsyendrasana"), [1, 2]);
+    assert_eq!(ids(&data, "Tadasana"), [3]);
+    let category_ids = data.categories.iter().map(|category| category.category_id).collect::<Vec<i32>>();
+    for asana in data.asanas.iter() {
+        assert!(asana.category_ids.iter().all(|id| category_ids.contains(id)), "{}", asana.sanskrit);
+    }
+}
+
+#[test]
+fn categories_round_trip() {
+    let path = scratch_db("categories");
+    let mut data = two_asanas();
+    data.categories = vec![
+        Category {
+            category_id: 1,
+            name: "Seated".to_string(),
+            description: None,
+        },
+        Category {
+            category_id: 3,
+            name: "Standing".to_string(),
+            description: Some("on the feet".to_string()),
+        },
+    ];
+    data.asanas[0].category_ids = vec![3];
+    data.asanas[1].category_ids = vec![1, 3];
+    db::write_asana_data(&path, &data).unwrap();
+    let read = db::read_asana_data(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.categories, data.categories);
+    assert_eq!(ids(&read, "Tadasana"), [3]);
+    assert_eq!(ids(&read, "Virasana"), [1, 3]);
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use std::collections::HashMap;
+
+use bincode::Encode;
+use common::{asana, two_asanas};
+use yogamat_wasm::{
+    dristi::Dristi,
+    format::{self, FormatError, FORMAT_VERSION, MAGIC},
+    skeleton::Joint,
+    Category,
+};
+
+/// Where the payload starts, after the magic, version, checksum and length.
+const HEADER_LEN: usize = 24;
+
+/// A joint as out_db stored it before version 2.
+#[derive(Encode)]
+struct OldJoint {
+    joint_id: i32,
+    pose_id: i32,
+    up: [f32; 3],
+    forward: [f32; 3],
+    origin: [f32; 3],
+}
+
+impl OldJoint {
+    fn new(joint_id: i32, pose_id: i32) -> OldJoint {
+        OldJoint {
+            joint_id,
+            pose_id,
+            up: [0.0, 1.0, 0.0],
+            forward: [0.0, 0.0, 1.0],
+            origin: [1.0, 2.0, 3.0],
+        }
+    }
+}
+
+#[derive(Encode)]
+struct V0Asana {
+    asana_id: i32,
+    pose_id: i32,
+    sanskrit: String,
+    english: String,
+    notes: Option<String>,
+}
+
+#[derive(Encode)]
+struct V0AsanaData {
+    asanas: Vec<V0Asana>,
+    poses: HashMap<i32, Vec<OldJoint>>,
+}
+
+/// `payload` behind a header claiming `version`.
+fn with_header(version: u32, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&format::crc32(payload).to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+fn set_version(bytes: &mut [u8], version: u32) {
+    bytes[8..12].copy_from_slice(&version.to_le_bytes());
+}
+
+#[test]
+fn round_trips() {
+    let data = two_asanas();
+    let bytes = format::encode(&data).unwrap();
+    assert!(bytes.starts_with(&MAGIC));
+    assert_eq!(bytes[8..12], FORMAT_VERSION.to_le_bytes());
+    assert_eq!(format::decode(&bytes).unwrap(), data);
+}
+
+#[test]
+fn corrupt_payload() {
+    let mut bytes = format::encode(&two_asanas()).unwrap();
+    let expected = format::crc32(&bytes[HEADER_LEN..]);
+    *bytes.last_mut().unwrap() ^= 0xff;
+    let found = format::crc32(&bytes[HEADER_LEN..]);
+
+    let error = format::decode(&bytes).unwrap_err();
+    assert!(
+        matches!(error, FormatError::ChecksumMismatch { expected: e, found: f } if e == expected && f == found),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn truncated() {
+    let bytes = format::encode(&two_asanas()).unwrap();
+    for length in [MAGIC.len(), HEADER_LEN - 1, HEADER_LEN, bytes.len() - 1] {
+        let error = format::decode(&bytes[..length]).unwrap_err();
+        assert!(matches!(error, FormatError::Truncated), "{length} bytes: {error:?}");
+    }
+}
+
+#[test]
+fn unsupported_versions() {
+    let mut bytes = format::encode(&two_asanas()).unwrap();
+    set_version(&mut bytes, FORMAT_VERSION + 1);
+    let error = format::decode(&bytes).unwrap_err();
+    assert!(matches!(error, FormatError::UnsupportedVersion(v) if v == FORMAT_VERSION + 1));
+    assert!(error.to_string().contains("newer"), "{error}");
+
+    set_version(&mut bytes, 0);
+    let error = format::decode(&bytes).unwrap_err();
+    assert!(matches!(error, FormatError::UnsupportedVersion(0)));
+    assert!(error.to_string().contains("never written"), "{error}");
+}
+
+#[test]
+fn headerless_v0() {
+    let old = V0AsanaData {
+        asanas: vec![V0Asana {
+            asana_id: 1,
+            pose_id: 10,
+            sanskrit: "Tadasana".to_string(),
+            english: "Mountain".to_string(),
+            notes: Some("feet together".to_string()),
+        }],
+        poses: HashMap::from([(10, vec![OldJoint::new(1, 10), OldJoint::new(2, 10)])]),
+    };
+    let bytes = bincode::encode_to_vec(&old, bincode::config::legacy()).unwrap();
+
+    let data = format::decode(&bytes).unwrap();
+    let asana = &data.asanas[0];
+    assert_eq!((asana.asana_id, asana.pose_id), (1, 10));
+    assert_eq!(asana.sanskrit, "Tadasana");
+    assert_eq!(asana.notes.as_deref(), Some("feet together"));
+    assert_eq!(asana.unicode, None);
+    assert_eq!(asana.dristi, None);
+    assert!(asana.category_ids.is_empty() && data.categories.is_empty());
+    assert_eq!(data.poses[&10].len(), 2);
+}
+
+/// A version 1 payload, as bincode wrote it before joints carried Euler
+/// angles: Tadasana (Tāḍāsana, dristi Nose, category 3) with one joint and
+/// the Standing category.
+const V1_PAYLOAD: [u8; 93] = [
+    0x01, 0x02, 0x14, 0x08, 0x54, 0x61, 0x64, 0x61, 0x73, 0x61, 0x6e, 0x61, 0x08, 0x4d, 0x6f, 0x75,
+    0x6e, 0x74, 0x61, 0x69, 0x6e, 0x01, 0x0c, 0x54, 0xc4, 0x81, 0xe1, 0xb8, 0x8d, 0xc4, 0x81, 0x73,
+    0x61, 0x6e, 0x61, 0x00, 0x01, 0x06, 0x01, 0x01, 0x01, 0x14, 0x01, 0x02, 0x14, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x40,
+    0x40, 0x01, 0x06, 0x08, 0x53, 0x74, 0x61, 0x6e, 0x64, 0x69, 0x6e, 0x67, 0x00,
+];
+
+#[test]
+fn v1_migrates() {
+    let data = format::decode(&with_header(1, &V1_PAYLOAD)).unwrap();
+    let mut expected = asana(1, 10, "Tadasana");
+    expected.english = "Mountain".to_string();
+    expected.unicode = Some("Tāḍāsana".to_string());
+    expected.category_ids = vec![3];
+    expected.dristi = Some(Dristi::Nose);
+    assert_eq!(data.asanas, [expected]);
+    let standing = Category {
+        category_id: 3,
+        name: "Standing".to_string(),
+        description: None,
+    };
+    assert_eq!(data.categories, [standing]);
+    let joint = Joint {
+        joint_id: 1,
+        pose_id: 10,
+        up_y: 1.0,
+        forward_z: 1.0,
+        origin_x: 1.0,
+        origin_y: 2.0,
+        origin_z: 3.0,
+        angles: None,
+        ..Joint::default()
+    };
+    assert_eq!(data.poses[&10], [joint]);
+}
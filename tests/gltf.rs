@@ -0,0 +1,109 @@
+mod common;
+
+use std::collections::HashMap;
+
+use bevy::math::{Mat4, Quat};
+use common::out_db;
+use gltf::{animation::util::ReadOutputs, Gltf};
+use yogamat_wasm::{
+    gltf::sequence_glb,
+    sequence::{self, Pacing},
+    skeleton,
+};
+
+/// Parsing with the `gltf` crate checks the document's structure and that
+/// every index in it points at something, not the whole glTF 2.0 schema.
+fn export(pacing: Pacing, sequence: &sequence::Sequence) -> Gltf {
+    let glb = sequence_glb(&out_db(), sequence, pacing).unwrap();
+    Gltf::from_slice(&glb).unwrap()
+}
+
+/// Global matrix of every node, found by walking down from the scene.
+fn node_globals(gltf: &Gltf) -> HashMap<usize, Mat4> {
+    let mut globals = HashMap::new();
+    let scene = gltf.default_scene().unwrap();
+    let mut stack = scene.nodes().map(|node| (node, Mat4::IDENTITY)).collect::<Vec<_>>();
+    while let Some((node, parent)) = stack.pop() {
+        let global = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+        globals.insert(node.index(), global);
+        stack.extend(node.children().map(|child| (child, global)));
+    }
+    globals
+}
+
+#[test]
+fn skin_is_bound_in_the_rest_pose() {
+    let gltf = export(Pacing::Seconds, &sequence::surya_namaskar_a());
+    let blob = gltf.blob.as_deref().unwrap();
+    assert_eq!(gltf.buffers().next().unwrap().length(), blob.len());
+    for view in gltf.views() {
+        assert!(view.offset() % 4 == 0 && view.offset() + view.length() <= blob.len());
+    }
+
+    let skin = gltf.skins().next().unwrap();
+    let joints = skin.joints().collect::<Vec<_>>();
+    assert_eq!(joints.len(), skeleton::bones().len());
+    let inverse_binds = skin
+        .reader(|_| Some(blob))
+        .read_inverse_bind_matrices()
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(inverse_binds.len(), joints.len());
+    // A skinned vertex lands where it was modelled until the bones move.
+    let globals = node_globals(&gltf);
+    for (joint, inverse_bind) in joints.iter().zip(inverse_binds.iter()) {
+        let skinning = globals[&joint.index()] * Mat4::from_cols_array_2d(inverse_bind);
+        assert!(
+            skinning.abs_diff_eq(Mat4::IDENTITY, 1e-4),
+            "{:?} isn't bound in its rest pose",
+            joint.name()
+        );
+    }
+
+    let primitive = gltf.meshes().next().unwrap().primitives().next().unwrap();
+    let reader = primitive.reader(|_| Some(blob));
+    let count = reader.read_positions().unwrap().count();
+    assert_eq!(reader.read_normals().unwrap().count(), count);
+    for (joints, weights) in reader
+        .read_joints(0)
+        .unwrap()
+        .into_u16()
+        .zip(reader.read_weights(0).unwrap().into_f32())
+    {
+        assert!((joints[0] as usize) < skin.joints().count());
+        assert_eq!(weights.iter().sum::<f32>(), 1.0);
+    }
+}
+
+#[test]
+fn animation_plays_every_step() {
+    for (pacing, sequence) in [
+        (Pacing::Seconds, sequence::surya_namaskar_a()),
+        (Pacing::Breath { per_minute: 7.5 }, sequence::surya_namaskar_b()),
+    ] {
+        // The animation starts in the first asana, without moving into it.
+        let duration = sequence
+            .steps
+            .iter()
+            .map(|step| pacing.timing(step))
+            .map(|(transition, hold)| transition + hold)
+            .sum::<f32>()
+            - pacing.timing(&sequence.steps[0]).0;
+        let gltf = export(pacing, &sequence);
+        let blob = gltf.blob.as_deref().unwrap();
+        let animation = gltf.animations().next().unwrap();
+        assert_eq!(animation.channels().count(), skeleton::bones().len() * 2);
+        for channel in animation.channels() {
+            let reader = channel.reader(|_| Some(blob));
+            let times = reader.read_inputs().unwrap().collect::<Vec<f32>>();
+            assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!((times.last().unwrap() - duration).abs() < 1e-3);
+            if let ReadOutputs::Rotations(rotations) = reader.read_outputs().unwrap() {
+                let rotations = rotations.into_f32().map(Quat::from_array).collect::<Vec<Quat>>();
+                assert_eq!(rotations.len(), times.len());
+                assert!(rotations.iter().all(|rotation| rotation.is_normalized()));
+                assert!(rotations.windows(2).all(|pair| pair[0].dot(pair[1]) >= 0.0));
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{joints, out_db};
+use yogamat_wasm::{
+    grounding::{self, mat_top},
+    kinematics::forward_kinematics,
+    skeleton,
+};
+
+#[test]
+fn every_pose_rests_on_the_mat() {
+    let data = out_db();
+    let bones = skeleton::bones();
+    let home = Transform::from_xyz(30.0, 40.0, -10.0).with_rotation(Quat::from_rotation_y(0.5));
+    for name in ["Tadasana", "Savasana", "Sirsasana A", "Padmasana"] {
+        let posed = forward_kinematics(bones, Mat4::IDENTITY, joints(&data, name)).unwrap();
+        let placed = grounding::ground(bones, &posed, home, None);
+        let (min, _max) = grounding::bounds(bones, &posed, placed.compute_matrix()).unwrap();
+        assert!((min.y - mat_top()).abs() < 1e-3, "{name} rests at {}", min.y);
+        assert_eq!((placed.translation.x, placed.translation.z), (home.translation.x, home.translation.z));
+        assert_eq!(placed.rotation, home.rotation);
+    }
+}
+
+#[test]
+fn centred_poses_sit_over_the_centre() {
+    let data = out_db();
+    let bones = skeleton::bones();
+    let posed = forward_kinematics(bones, Mat4::IDENTITY, joints(&data, "Savasana")).unwrap();
+    let centre = Vec3::new(5.0, 0.0, -3.0);
+    let placed = grounding::ground(bones, &posed, Transform::IDENTITY, Some(centre));
+    let (min, max) = grounding::bounds(bones, &posed, placed.compute_matrix()).unwrap();
+    let middle = (min + max) / 2.0;
+    assert!((min.y - mat_top()).abs() < 1e-3);
+    assert!(Vec2::new(middle.x - centre.x, middle.z - centre.z).length() < 1e-3);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{joints, out_db};
+use yogamat_wasm::{
+    ik::{self, Chain, Goal, CHAINS},
+    limits::range_of_motion,
+    skeleton::{self, Joint},
+};
+
+fn local(pose: &[Joint], joint_id: i32) -> Transform {
+    Transform::from_matrix(pose.iter().find(|joint| joint.joint_id == joint_id).unwrap().matrix())
+}
+
+/// Where the parent of `chain` is in `pose`, relative to the hips' parent.
+fn parent(chain: &Chain, pose: &[Joint]) -> Transform {
+    let above = skeleton::parent(chain.joints[0]).unwrap();
+    skeleton::ancestors(above)
+        .into_iter()
+        .rev()
+        .fold(Transform::IDENTITY, |global, joint_id| global.mul_transform(local(pose, joint_id)))
+}
+
+fn locals(chain: &Chain, pose: &[Joint]) -> Vec<Transform> {
+    chain.joints.iter().map(|joint_id| local(pose, *joint_id)).collect()
+}
+
+fn end(parent: Transform, locals: &[Transform]) -> Vec3 {
+    ik::globals(parent, locals).last().unwrap().translation
+}
+
+fn assert_within_limits(chain: &Chain, locals: &[Transform]) {
+    for (joint_id, local) in chain.joints.iter().zip(locals) {
+        let exceeded = range_of_motion(*joint_id).exceeded(local.rotation);
+        assert!(exceeded.is_empty(), "{} joint {joint_id} is past {exceeded:?}", chain.name);
+    }
+}
+
+#[test]
+fn reaches_where_another_pose_put_the_limb() {
+    let data = out_db();
+    let tadasana = joints(&data, "Tadasana");
+    for name in ["Chaturanga Dandasana", "Utkatasana", "Virabhadrasana A", "Garudasana"] {
+        let other = joints(&data, name);
+        for chain in CHAINS.iter() {
+            let parent = parent(chain, tadasana);
+            let goal = Goal {
+                position: end(parent, &locals(chain, other)),
+                rotation: None,
+                pole: None,
+            };
+            let mut locals = locals(chain, tadasana);
+            let left = ik::solve(chain, parent, &mut locals, &goal);
+            assert!(left < 0.1, "{} missed {name} by {left}", chain.name);
+            assert!((end(parent, &locals) - goal.position).length() < 0.1);
+            assert_within_limits(chain, &locals);
+        }
+    }
+}
+
+#[test]
+fn stretches_towards_a_target_out_of_reach() {
+    let data = out_db();
+    let tadasana = joints(&data, "Tadasana");
+    let chain = &CHAINS[0];
+    let parent = parent(chain, tadasana);
+    let mut locals = locals(chain, tadasana);
+    let shoulder = ik::globals(parent, &locals)[1].translation;
+    let goal = Goal {
+        position: shoulder + Vec3::new(0.0, 0.0, 200.0),
+        rotation: None,
+        pole: None,
+    };
+    let left = ik::solve(chain, parent, &mut locals, &goal);
+    assert!(left > 100.0);
+    assert!((end(parent, &locals) - goal.position).length() - left < 1e-3);
+    assert_within_limits(chain, &locals);
+}
+
+#[test]
+fn keeps_to_the_pole_and_the_goal_rotation() {
+    let data = out_db();
+    let tadasana = joints(&data, "Tadasana");
+    let chain = &CHAINS[2];
+    let parent = parent(chain, tadasana);
+    let mut locals = locals(chain, tadasana);
+    let globals = ik::globals(parent, &locals);
+    let (hip, foot) = (globals[0].translation, globals[2].translation);
+    // Lift the foot a third of the way to the hip, knee forward, keeping
+    // it flat.
+    let goal = Goal {
+        position: foot.lerp(hip, 0.3),
+        rotation: Some(globals[2].rotation),
+        pole: chain.pole(parent, &locals),
+    };
+    let left = ik::solve(chain, parent, &mut locals, &goal);
+    assert!(left < 0.1, "missed by {left}");
+    assert_within_limits(chain, &locals);
+    let globals = ik::globals(parent, &locals);
+    assert!(globals[2].rotation.angle_between(goal.rotation.unwrap()) < 0.01);
+    let knee = globals[1].translation - hip.lerp(goal.position, 0.5);
+    assert!(knee.dot(goal.pole.unwrap() - hip) > 0.0, "the knee bent away from the pole");
+}
+
+#[test]
+fn chains_follow_the_skeleton() {
+    let joints = CHAINS.iter().map(|chain| chain.joints.as_slice()).collect::<Vec<&[i32]>>();
+    assert_eq!(joints, [&[33, 34, 35, 36][..], &[37, 38, 39, 40], &[2, 3, 4], &[5, 6, 7]]);
+    for chain in CHAINS.iter() {
+        for pair in chain.joints.windows(2) {
+            assert_eq!(skeleton::parent(pair[1]), Some(pair[0]), "{}", chain.name);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use bevy::{prelude::*, transform::TransformPlugin};
+use common::{joints, out_db};
+use yogamat_wasm::{
+    kinematics::{forward_kinematics, pose_skeleton, MissingJoint, PosedSkeleton},
+    proportions::BodyProportions,
+    skeleton::{self, SkeletonBone},
+};
+
+/// Spawns `bones` under an empty at `root` the way the app does and lets
+/// Bevy propagate the transforms.
+fn bevy_globals(bones: &[SkeletonBone], root: Transform, matrices: &[(i32, Mat4)]) -> Vec<(i32, GlobalTransform)> {
+    let mut app = App::new();
+    app.add_plugins(TransformPlugin);
+    let world = app.world_mut();
+    let empty = world.spawn(root).id();
+    let mut entities: Vec<(i32, Entity)> = Vec::new();
+    for bone in bones {
+        let (_joint_id, mat) = matrices
+            .iter()
+            .find(|(joint_id, _mat)| *joint_id == bone.joint_id)
+            .unwrap();
+        let parent = bone.parent.map_or(empty, |parent| {
+            entities.iter().find(|(joint_id, _entity)| *joint_id == parent).unwrap().1
+        });
+        let entity = world.spawn((Transform::from_matrix(*mat), ChildOf(parent))).id();
+        entities.push((bone.joint_id, entity));
+    }
+    app.update();
+    entities
+        .into_iter()
+        .map(|(joint_id, entity)| (joint_id, *app.world().get::<GlobalTransform>(entity).unwrap()))
+        .collect()
+}
+
+fn assert_matches_bevy(bones: &[SkeletonBone], root: Transform, matrices: &[(i32, Mat4)], posed: &PosedSkeleton) {
+    let globals = bevy_globals(bones, root, matrices);
+    assert_eq!(posed.bones.len(), globals.len());
+    for (bone, (joint_id, global)) in bones.iter().zip(globals) {
+        let placed = posed.bone(joint_id).unwrap();
+        assert!(
+            placed.global.abs_diff_eq(global.compute_matrix(), 1e-3),
+            "joint {joint_id}: {} != {}",
+            placed.global,
+            global.compute_matrix()
+        );
+        assert!(placed.start.abs_diff_eq(global.translation(), 1e-3));
+        let end = bone.cube.transform.translation - Vec3::Y * bone.cube.y / 2.0;
+        assert!(placed.end.abs_diff_eq(global.transform_point(end), 1e-3));
+    }
+}
+
+#[test]
+fn matches_bevy_hierarchy() {
+    let data = out_db();
+    let bones = skeleton::bones();
+    let root = Transform::from_xyz(50.0, 0.0, 0.0).with_scale(Vec3::splat(0.5));
+    for sanskrit in ["Tadasana", "Vrksasana - 1", "Ardha Matsyendrasana", "Adho Mukha Vrksasana"] {
+        let joints = joints(&data, sanskrit);
+        let matrices = joints
+            .iter()
+            .map(|joint| (joint.joint_id, joint.matrix()))
+            .collect::<Vec<(i32, Mat4)>>();
+        let posed = forward_kinematics(bones, root.compute_matrix(), joints).unwrap();
+        assert_matches_bevy(bones, root, &matrices, &posed);
+    }
+}
+
+#[test]
+fn retargeted_body_matches_bevy() {
+    let data = out_db();
+    let body = BodyProportions::CHILD;
+    let bones = body.bones();
+    let matrices = joints(&data, "Virabhadrasana A")
+        .iter()
+        .map(|joint| (joint.joint_id, body.retarget_matrix(joint.joint_id, joint.matrix())))
+        .collect::<Vec<(i32, Mat4)>>();
+    let posed = pose_skeleton(&bones, Mat4::IDENTITY, &matrices).unwrap();
+    assert_matches_bevy(&bones, Transform::IDENTITY, &matrices, &posed);
+}
+
+#[test]
+fn tadasana_stands_on_the_mat() {
+    let data = out_db();
+    let posed = forward_kinematics(skeleton::bones(), Mat4::IDENTITY, joints(&data, "Tadasana")).unwrap();
+    let hips = posed.bone(1).unwrap();
+    assert!(hips.start.abs_diff_eq(Vec3::new(-5.0, -46.0, 0.0), 1e-3));
+    // The feet are level, just above the mat's top at y = -109.
+    let left = posed.bone(4).unwrap().start;
+    let right = posed.bone(7).unwrap().start;
+    assert!((left.y - right.y).abs() < 0.5);
+    assert!(left.z < 0.0 && right.z > 0.0, "left is -z");
+    let lowest = posed.bones.iter().map(|bone| bone.end.y.min(bone.start.y)).fold(f32::MAX, f32::min);
+    assert!((-112.0..-105.0).contains(&lowest), "{lowest}");
+    // The head is on top.
+    assert_eq!(
+        posed
+            .bones
+            .iter()
+            .max_by(|a, b| a.end.y.max(a.start.y).total_cmp(&b.end.y.max(b.start.y)))
+            .map(|bone| bone.joint_id),
+        Some(32)
+    );
+}
+
+#[test]
+fn missing_joint() {
+    let data = out_db();
+    let mut joints = joints(&data, "Tadasana").to_vec();
+    joints.retain(|joint| joint.joint_id != 35);
+    assert_eq!(
+        forward_kinematics(skeleton::bones(), Mat4::IDENTITY, &joints).unwrap_err(),
+        MissingJoint(35)
+    );
+}
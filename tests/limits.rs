@@ -0,0 +1,97 @@
+mod common;
+
+use bevy::math::{Quat, Vec3};
+use common::{joints, out_db};
+use yogamat_wasm::{
+    limits::{self, range_of_motion},
+    skeleton::{self, Joint},
+};
+
+const LEFT_CALF: i32 = 3;
+const RIGHT_CALF: i32 = 6;
+const LEFT_FOREARM: i32 = 35;
+const RIGHT_FOREARM: i32 = 39;
+
+fn motion(joints: &[Joint], joint_id: i32) -> Vec3 {
+    let joint = joints.iter().find(|joint| joint.joint_id == joint_id).unwrap();
+    range_of_motion(joint_id).motion(joint.rotation())
+}
+
+#[test]
+fn standing_is_neutral() {
+    let data = out_db();
+    let tadasana = joints(&data, "Tadasana");
+    assert_eq!(limits::pose_violations(tadasana), Vec::new());
+    for joint_id in [LEFT_CALF, RIGHT_CALF, LEFT_FOREARM, RIGHT_FOREARM] {
+        assert!(motion(tadasana, joint_id).x.abs() < 1.0);
+    }
+}
+
+#[test]
+fn bent_elbows_flex_negatively() {
+    let data = out_db();
+    for name in ["Chaturanga Dandasana", "Sirsasana A", "Malasana"] {
+        let pose = joints(&data, name);
+        assert_eq!(limits::pose_violations(pose), Vec::new(), "{name}");
+        for joint_id in [LEFT_FOREARM, RIGHT_FOREARM] {
+            let flexion = motion(pose, joint_id).x;
+            assert!(flexion < -80.0, "{name} bends joint {joint_id} by {flexion}");
+        }
+    }
+}
+
+#[test]
+fn bent_knees_flex_positively() {
+    let data = out_db();
+    for name in ["Virasana", "Padmasana", "Malasana", "Baddha Konasana A", "Utkatasana"] {
+        let pose = joints(&data, name);
+        assert_eq!(limits::pose_violations(pose), Vec::new(), "{name}");
+        for joint_id in [LEFT_CALF, RIGHT_CALF] {
+            let flexion = motion(pose, joint_id).x;
+            assert!(flexion > 60.0, "{name} bends joint {joint_id} by {flexion}");
+        }
+    }
+}
+
+#[test]
+fn no_elbow_bends_backwards() {
+    let data = out_db();
+    let mut bent_back = Vec::new();
+    for asana in data.asanas.iter() {
+        for joint_id in [LEFT_FOREARM, RIGHT_FOREARM] {
+            let flexion = motion(&data.poses[&asana.pose_id], joint_id).x;
+            if flexion > limits::ELBOW.max.x {
+                bent_back.push((asana.sanskrit.as_str(), joint_id));
+            }
+        }
+    }
+    // Vrksasana - 1 has its upper arms turned half way round, which looks
+    // the same with the elbows bent back.
+    assert_eq!(bent_back, [("Vrksasana - 1", LEFT_FOREARM), ("Vrksasana - 1", RIGHT_FOREARM)]);
+}
+
+#[test]
+fn right_joints_mirror_the_left() {
+    for bone in skeleton::bones() {
+        let opposite = skeleton::opposite(bone.joint_id);
+        if opposite != bone.joint_id {
+            assert_eq!(range_of_motion(opposite), range_of_motion(bone.joint_id).mirrored());
+        }
+    }
+    // Vertebrae after the first of their run carry straight on.
+    assert_eq!(range_of_motion(8).neutral, limits::LUMBAR.neutral);
+    assert_eq!(range_of_motion(9).neutral, Vec3::ZERO);
+}
+
+#[test]
+fn motion_round_trips_and_clamps() {
+    let rom = range_of_motion(LEFT_FOREARM);
+    let motion = Vec3::new(-70.0, 40.0, 10.0);
+    assert!(rom.motion(rom.rotation(motion)).abs_diff_eq(motion, 1e-2));
+    let too_far = rom.rotation(Vec3::new(60.0, 40.0, 10.0));
+    assert_eq!(rom.exceeded(too_far).len(), 1);
+    let clamped = rom.clamp(too_far);
+    assert!(rom.exceeded(clamped).is_empty());
+    assert!((rom.motion(clamped).x - rom.max.x).abs() < 1e-2);
+    assert_eq!(rom.clamp(Quat::IDENTITY), Quat::IDENTITY);
+}
@@ -0,0 +1,95 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use bevy::{
+    asset::{io::AssetReaderError, AssetLoadError, LoadState},
+    prelude::*,
+};
+use common::two_asanas;
+use yogamat_wasm::{
+    format,
+    loader::{self, PoseDatabase, PoseDatabasePlugin},
+};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn command_line_comes_first() {
+    let env = Some("from/env".to_string());
+    assert_eq!(loader::database_path(args(&["--db", "from/args"]), env.clone()), PathBuf::from("from/args"));
+    assert_eq!(
+        loader::database_path(args(&["--verbose", "--db", "poses", "--other"]), None),
+        PathBuf::from("poses")
+    );
+    assert_eq!(loader::database_path(args(&["--verbose"]), env.clone()), PathBuf::from("from/env"));
+    // `--db` without a path leaves it to the environment.
+    assert_eq!(loader::database_path(args(&["--db"]), env), PathBuf::from("from/env"));
+    assert_eq!(loader::database_path(args(&[]), None), PathBuf::from("out_db"));
+}
+
+/// Loads `path` through the asset server the way the app does and returns
+/// how loading ended.
+fn load(path: &Path) -> Result<PoseDatabase, Arc<AssetLoadError>> {
+    let mut app = App::new();
+    let asset_path = loader::register_source(&mut app, path);
+    app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default(), PoseDatabasePlugin));
+    let handle = app.world().resource::<AssetServer>().load::<PoseDatabase>(asset_path);
+    for _ in 0..500 {
+        app.update();
+        match app.world().resource::<AssetServer>().get_load_state(&handle) {
+            Some(LoadState::Loaded) => {
+                let mut databases = app.world_mut().resource_mut::<Assets<PoseDatabase>>();
+                return Ok(databases.remove(&handle).unwrap());
+            }
+            Some(LoadState::Failed(e)) => return Err(e),
+            _ => std::thread::sleep(Duration::from_millis(10)),
+        }
+    }
+    panic!("{} neither loaded nor failed", path.display());
+}
+
+fn scratch_file(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("yogamat-{name}-{}", std::process::id()));
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[test]
+fn loads_out_db() {
+    let path = scratch_file("loads", &format::encode(&two_asanas()).unwrap());
+    let loaded = load(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap().0, two_asanas());
+}
+
+#[test]
+fn missing_file() {
+    let path = std::env::temp_dir().join(format!("yogamat-missing-{}", std::process::id()));
+    let error = load(&path).err().unwrap();
+    assert!(
+        matches!(*error, AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_))),
+        "{error}"
+    );
+}
+
+#[test]
+fn corrupt_file() {
+    let mut bytes = format::encode(&two_asanas()).unwrap();
+    *bytes.last_mut().unwrap() ^= 0xff;
+    let path = scratch_file("corrupt", &bytes);
+    let error = load(&path).err().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(error.to_string().contains("bad pose database: checksum mismatch"), "{error}");
+}
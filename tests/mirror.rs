@@ -0,0 +1,79 @@
+mod common;
+
+use common::{joints, out_db};
+use yogamat_wasm::{
+    mirror::{self, SUFFIX, SYMMETRY_TOLERANCE},
+    skeleton::{self, Joint},
+};
+
+#[test]
+fn left_and_right_bones_swap() {
+    for bone in skeleton::bones() {
+        let opposite = skeleton::opposite(bone.joint_id);
+        assert_eq!(skeleton::opposite(opposite), bone.joint_id);
+        let other = &skeleton::find_bone(opposite).unwrap().cube.name;
+        let expected = if let Some(rest) = bone.cube.name.strip_prefix("Left ") {
+            format!("Right {rest}")
+        } else if let Some(rest) = bone.cube.name.strip_prefix("Right ") {
+            format!("Left {rest}")
+        } else {
+            bone.cube.name.clone()
+        };
+        assert_eq!(*other, expected);
+    }
+}
+
+#[test]
+fn mirroring_twice_changes_nothing() {
+    let data = out_db();
+    for asana in data.asanas.iter() {
+        let pose = &data.poses[&asana.pose_id];
+        let twice = mirror::mirror_pose(&mirror::mirror_pose(pose));
+        for (joint, back) in pose.iter().zip(twice.iter()) {
+            assert_eq!(joint.joint_id, back.joint_id);
+            assert!(
+                joint.matrix().abs_diff_eq(back.matrix(), 1e-4),
+                "{} joint {} changed",
+                asana.sanskrit,
+                joint.joint_id
+            );
+        }
+    }
+}
+
+#[test]
+fn standing_is_symmetric() {
+    let data = out_db();
+    let asymmetry = mirror::asymmetry(joints(&data, "Tadasana")).unwrap();
+    assert!(asymmetry < SYMMETRY_TOLERANCE, "Tadasana is {asymmetry} off");
+    let asymmetry = mirror::asymmetry(joints(&data, "Utthita Trikonasana")).unwrap();
+    assert!(asymmetry > SYMMETRY_TOLERANCE);
+}
+
+#[test]
+fn other_sides_are_added_once() {
+    let mut data = out_db();
+    let asymmetric = mirror::asymmetric(&data);
+    let added = mirror::add_mirrored(&mut data, &asymmetric);
+    assert_eq!(added.len(), asymmetric.len());
+    for (original, mirrored) in added.iter() {
+        let find = |asana_id: i32| data.asanas.iter().find(|asana| asana.asana_id == asana_id).unwrap();
+        let (original, mirrored) = (find(*original), find(*mirrored));
+        assert_eq!(mirrored.sanskrit, format!("{}{SUFFIX}", original.sanskrit.trim()));
+        let expected = mirror::mirror_pose(&data.poses[&original.pose_id])
+            .into_iter()
+            .map(|joint| Joint {
+                pose_id: mirrored.pose_id,
+                ..joint
+            })
+            .collect::<Vec<Joint>>();
+        assert_eq!(data.poses[&mirrored.pose_id], expected);
+    }
+
+    let count = data.asanas.len();
+    assert_eq!(mirror::add_mirrored(&mut data, &asymmetric), Vec::new());
+    // Mirrored variants aren't mirrored back.
+    let mirrored = added.iter().map(|(_original, mirrored)| *mirrored).collect::<Vec<i32>>();
+    assert_eq!(mirror::add_mirrored(&mut data, &mirrored), Vec::new());
+    assert_eq!(data.asanas.len(), count);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use bevy::math::Mat4;
+use common::two_asanas;
+use yogamat_wasm::{
+    pose::{find_asana, load_pose, pose_matrices, PoseError, TADASANA},
+    validate::JOINT_IDS,
+    Asana, AsanaData,
+};
+
+fn all_joint_ids() -> Vec<i32> {
+    JOINT_IDS.collect()
+}
+
+fn virasana(data: &AsanaData) -> &Asana {
+    find_asana(data, 170).unwrap()
+}
+
+#[test]
+fn intact_pose_loads() {
+    let data = two_asanas();
+    let pose = load_pose(&data, virasana(&data)).unwrap();
+    assert_eq!(pose.len(), 40);
+
+    let matrices = pose_matrices(&data, virasana(&data), &all_joint_ids()).unwrap();
+    assert_eq!(matrices.len(), 40);
+    assert!(matrices.iter().all(|(_joint_id, mat)| *mat == Mat4::IDENTITY));
+}
+
+#[test]
+fn asanas_sharing_a_name() {
+    let mut data = two_asanas();
+    data.asanas.insert(0, common::asana(3, 10, "Virasana"));
+    data.poses.get_mut(&20).unwrap().clear();
+
+    // Each asana is posed with its own pose, whichever comes first by name.
+    assert!(pose_matrices(&data, find_asana(&data, 3).unwrap(), &all_joint_ids()).is_ok());
+    assert!(pose_matrices(&data, virasana(&data), &all_joint_ids()).is_err());
+}
+
+#[test]
+fn missing_pose() {
+    let mut data = two_asanas();
+    data.poses.remove(&20);
+
+    assert_eq!(
+        load_pose(&data, virasana(&data)).unwrap_err(),
+        PoseError::PoseMissing {
+            sanskrit: "Virasana".to_string(),
+            pose_id: 20
+        }
+    );
+}
+
+#[test]
+fn missing_joint() {
+    let mut data = two_asanas();
+    data.poses.get_mut(&20).unwrap().retain(|joint| joint.joint_id != 33);
+
+    // Only a skeleton that needs the joint fails.
+    assert!(pose_matrices(&data, virasana(&data), &[1, 2, 3]).is_ok());
+    assert_eq!(
+        pose_matrices(&data, virasana(&data), &all_joint_ids()).unwrap_err(),
+        PoseError::JointMissing {
+            sanskrit: "Virasana".to_string(),
+            joint_id: 33
+        }
+    );
+}
+
+#[test]
+fn nan_joint() {
+    let mut data = two_asanas();
+    data.poses.get_mut(&20).unwrap()[4].origin_y = f32::NAN;
+
+    let error = pose_matrices(&data, virasana(&data), &all_joint_ids()).unwrap_err();
+    assert_eq!(
+        error,
+        PoseError::BadJoint {
+            sanskrit: "Virasana".to_string(),
+            joint_id: 5
+        }
+    );
+    assert_eq!(error.to_string(), "Virasana: joint 5 has a NaN or infinite value");
+}
+
+#[test]
+fn broken_pose_leaves_others_alone() {
+    let mut data = two_asanas();
+    data.poses.get_mut(&20).unwrap().clear();
+
+    assert!(pose_matrices(&data, find_asana(&data, TADASANA).unwrap(), &all_joint_ids()).is_ok());
+    assert!(pose_matrices(&data, virasana(&data), &all_joint_ids()).is_err());
+}
+
+#[test]
+fn empty_data() {
+    let data = AsanaData::default();
+    let error = find_asana(&data, 1).unwrap_err();
+    assert_eq!(error, PoseError::AsanaNotFound(1));
+    assert_eq!(error.to_string(), "no asana has id 1");
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use bevy::math::Mat4;
+use common::{joints, out_db};
+use yogamat_wasm::{proportions::BodyProportions, skeleton::Joint};
+
+fn assert_same_joint(a: &Joint, b: &Joint) {
+    assert!(
+        a.matrix().abs_diff_eq(b.matrix(), 1e-4),
+        "joint {} moved from {:?} to {:?}",
+        a.joint_id,
+        a.matrix(),
+        b.matrix()
+    );
+}
+
+#[test]
+fn adult_body_is_the_default() {
+    let data = out_db();
+    for joint in joints(&data, "Utthita Trikonasana") {
+        assert_same_joint(joint, &BodyProportions::ADULT.retarget(joint));
+    }
+}
+
+#[test]
+fn retargeting_round_trips() {
+    let data = out_db();
+    for (name, body) in BodyProportions::PRESETS {
+        for joint in joints(&data, "Utthita Trikonasana") {
+            let retargeted = body.retarget(joint);
+            assert_same_joint(joint, &body.to_default_body(&retargeted));
+
+            let mat = body.retarget_matrix(joint.joint_id, joint.matrix());
+            assert!(mat.abs_diff_eq(retargeted.matrix(), 1e-4), "{name} retargets matrices differently");
+            let back = body.to_default_body_matrix(joint.joint_id, mat);
+            assert!(back.abs_diff_eq(joint.matrix(), 1e-4), "{name} doesn't bring matrices back");
+        }
+    }
+}
+
+#[test]
+fn switching_bodies_keeps_the_pose() {
+    let data = out_db();
+    let (child, tall) = (BodyProportions::CHILD, BodyProportions::TALL);
+    for joint in joints(&data, "Utthita Trikonasana") {
+        // What the app does to the bones when the body goes from child to tall.
+        let on_child = child.retarget_matrix(joint.joint_id, joint.matrix());
+        let on_tall = tall.retarget_matrix(joint.joint_id, child.to_default_body_matrix(joint.joint_id, on_child));
+        assert!(on_tall.abs_diff_eq(tall.retarget(joint).matrix(), 1e-4));
+        // Rotations are left alone, only origins move.
+        assert_eq!(Mat4 { w_axis: on_tall.w_axis, ..joint.matrix() }, on_tall);
+    }
+}
@@ -0,0 +1,166 @@
+use yogamat_wasm::sequence::{self, Pacing, Phase, Player, Sequence, Step};
+
+/// Three steps of one second moving in and one held.
+fn steps() -> Sequence {
+    let step = |asana_id| Step {
+        asana_id,
+        hold: 1.0,
+        transition: 1.0,
+        other_side: false,
+        inhales: 0,
+        exhales: 0,
+    };
+    Sequence {
+        name: "Steps".to_string(),
+        steps: vec![step(1), step(2), step(3)],
+    }
+}
+
+#[test]
+fn plays_through_and_stops_at_the_end() {
+    let mut player = Player::new(steps());
+    assert!(!player.is_playing());
+    assert_eq!(player.advance(10.0), None);
+    assert_eq!(player.step(), 0);
+
+    assert_eq!(player.play(), Some(0));
+    assert_eq!(player.advance(1.5), None);
+    assert_eq!(player.progress(), 0.25);
+    assert_eq!(player.advance(1.0), Some(1));
+    // Steps skipped over in one go report the last one reached.
+    assert_eq!(player.advance(2.0), Some(2));
+    assert_eq!(player.advance(10.0), None);
+    assert!(!player.is_playing());
+    assert_eq!(player.step(), 2);
+    assert_eq!(player.progress(), 1.0);
+
+    // Played again once finished, it starts over.
+    assert_eq!(player.play(), Some(0));
+    assert_eq!(player.progress(), 0.0);
+}
+
+#[test]
+fn pausing_keeps_its_place() {
+    let mut player = Player::new(steps());
+    player.play();
+    player.advance(2.5);
+    player.pause();
+    assert_eq!(player.advance(1.0), None);
+    assert_eq!(player.step(), 1);
+    // Resuming mid step doesn't move into it again.
+    assert_eq!(player.play(), None);
+    assert_eq!(player.advance(1.5), Some(2));
+}
+
+#[test]
+fn skips_between_steps() {
+    let mut player = Player::new(steps());
+    assert_eq!(player.previous_step(), None);
+    assert_eq!(player.next_step(), Some(1));
+    assert_eq!(player.next_step(), Some(2));
+    assert_eq!(player.next_step(), None);
+    assert_eq!(player.previous_step(), Some(1));
+    assert_eq!(player.current().unwrap().asana_id, 2);
+    assert_eq!(player.progress(), 1.0 / 3.0);
+    // Skipping lands at the start of the step, so playing moves into it.
+    assert_eq!(player.play(), Some(1));
+}
+
+#[test]
+fn empty_sequence_does_not_play() {
+    let mut player = Player::new(Sequence {
+        name: "Empty".to_string(),
+        steps: Vec::new(),
+    });
+    assert_eq!(player.play(), None);
+    assert_eq!(player.current(), None);
+    assert_eq!(player.next_step(), None);
+    assert_eq!(player.progress(), 0.0);
+}
+
+fn breathing(inhales: u32, exhales: u32) -> Step {
+    Step {
+        asana_id: 1,
+        hold: 0.0,
+        transition: 0.0,
+        other_side: false,
+        inhales,
+        exhales,
+    }
+}
+
+#[test]
+fn steps_breathe_their_own_counts() {
+    use Phase::*;
+    let phases = |step: Step| {
+        (0..=step.inhales + step.exhales)
+            .map(|breath| step.phase(breath))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(phases(breathing(1, 0)), [Some(Inhale), None]);
+    assert_eq!(phases(breathing(0, 1)), [Some(Exhale), None]);
+    assert_eq!(phases(breathing(1, 1)), [Some(Inhale), Some(Exhale), None]);
+    assert_eq!(
+        phases(breathing(2, 3)),
+        [Some(Exhale), Some(Inhale), Some(Exhale), Some(Inhale), Some(Exhale), None]
+    );
+    // Counts that can't alternate run the rest together at the end.
+    assert_eq!(phases(breathing(3, 1)), [Some(Inhale), Some(Exhale), Some(Inhale), Some(Inhale), None]);
+    assert_eq!(phases(breathing(0, 0)), [None]);
+}
+
+#[test]
+fn breathes_while_played_by_breath() {
+    // An inhale after a step of three breaths is still an inhale.
+    let mut player = Player::new(Sequence {
+        name: "Breaths".to_string(),
+        steps: vec![breathing(2, 1), breathing(1, 0), breathing(0, 1)],
+    });
+    assert_eq!(player.breath(), None);
+    // Four seconds a breath.
+    player.set_pacing(Pacing::Breath { per_minute: 7.5 });
+    assert_eq!(player.transition(), 4.0);
+    assert_eq!(player.play(), Some(0));
+    assert_eq!(player.breath(), Some((Phase::Inhale, 0.0)));
+    player.advance(5.0);
+    assert_eq!(player.breath(), Some((Phase::Exhale, 0.25)));
+    player.advance(4.0);
+    assert_eq!(player.breath(), Some((Phase::Inhale, 0.25)));
+    assert_eq!(player.advance(3.0), Some(1));
+    assert_eq!(player.breath(), Some((Phase::Inhale, 0.0)));
+    assert_eq!(player.next_step(), Some(2));
+    assert_eq!(player.breath(), Some((Phase::Exhale, 0.0)));
+    assert_eq!(player.previous_step(), Some(1));
+    assert_eq!(player.breath(), Some((Phase::Inhale, 0.0)));
+    assert_eq!(player.advance(8.0), Some(2));
+    assert!(!player.is_playing());
+    assert_eq!(player.breath(), Some((Phase::Exhale, 1.0)));
+    assert_eq!(player.progress(), 1.0);
+}
+
+#[test]
+fn breaths_pace_the_clock() {
+    // By the clock a movement takes a breath, half moving and half held.
+    let steps = sequence::surya_namaskar_a().steps;
+    assert!(steps.iter().all(|step| step.transition == 2.0));
+    let holds = steps.iter().map(|step| step.hold).collect::<Vec<_>>();
+    assert_eq!(holds, [2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 20.0, 2.0, 2.0, 2.0, 2.0]);
+    // By breath, downward dog is moved into on an exhale and held for five
+    // breaths.
+    let pacing = Pacing::Breath { per_minute: 7.5 };
+    assert_eq!(pacing.timing(&steps[6]), (4.0, 40.0));
+    assert_eq!(steps[6].phase(0), Some(Phase::Exhale));
+}
+
+#[test]
+fn unusable_breath_rates_fall_back_on_the_clock() {
+    let step = &steps().steps[0];
+    for per_minute in [0.0, -7.5, f32::NAN, f32::INFINITY] {
+        let pacing = Pacing::Breath { per_minute };
+        assert_eq!(pacing.timing(step), (1.0, 1.0), "at {per_minute} a minute");
+        let mut player = Player::new(steps());
+        player.set_pacing(pacing);
+        player.play();
+        assert_eq!(player.breath(), None);
+    }
+}
@@ -0,0 +1,152 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::out_db;
+use yogamat_wasm::{format, text, AsanaData};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yogamat-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// The tree only keeps poses an asana refers to.
+fn referenced(mut data: AsanaData) -> AsanaData {
+    let pose_ids = data.asanas.iter().map(|asana| asana.pose_id).collect::<Vec<i32>>();
+    data.poses.retain(|pose_id, _| pose_ids.contains(pose_id));
+    for joints in data.poses.values_mut() {
+        joints.sort_by_key(|joint| joint.joint_id);
+    }
+    data.asanas.sort_by_key(|asana| asana.asana_id);
+    data
+}
+
+#[test]
+fn tree_round_trips_out_db() {
+    let dir = scratch_dir("round-trip");
+    let data = out_db();
+    text::write_tree(&data, &dir).unwrap();
+    let from_tree = text::read_tree(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(from_tree, referenced(data));
+}
+
+#[test]
+fn bincode_round_trips_tree() {
+    let dir = scratch_dir("bincode");
+    text::write_tree(&out_db(), &dir).unwrap();
+    let from_tree = text::read_tree(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let decoded = format::decode(&format::encode(&from_tree).unwrap()).unwrap();
+    assert_eq!(referenced(decoded), from_tree);
+}
+
+#[test]
+fn one_file_per_asana() {
+    let dir = scratch_dir("files");
+    let data = out_db();
+    text::write_tree(&data, &dir).unwrap();
+    let names = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<String>>();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(names.len(), data.asanas.len() + 1);
+    assert!(names.iter().any(|name| name == text::CATEGORIES_FILE));
+    for asana in data.asanas.iter() {
+        assert!(names.contains(&text::file_name(asana)), "{}", asana.sanskrit);
+    }
+}
+
+#[test]
+fn rewriting_removes_stale_files() {
+    let dir = scratch_dir("stale");
+    let mut data = out_db();
+    text::write_tree(&data, &dir).unwrap();
+    let removed = data.asanas.pop().unwrap();
+    text::write_tree(&data, &dir).unwrap();
+    let stale = dir.join(text::file_name(&removed)).exists();
+    let from_tree = text::read_tree(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!stale);
+    assert_eq!(from_tree.asanas.len(), data.asanas.len());
+}
+
+#[test]
+fn broken_file_names_its_path() {
+    let dir = scratch_dir("broken");
+    let data = out_db();
+    text::write_tree(&data, &dir).unwrap();
+    let broken = dir.join(text::file_name(&data.asanas[0]));
+    fs::write(&broken, "{ \"asana_id\": ").unwrap();
+    let error = text::read_tree(&dir).unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(error.to_string().contains(&*broken.to_string_lossy()));
+}
+
+#[test]
+fn rewriting_keeps_other_files() {
+    let dir = scratch_dir("foreign");
+    let data = out_db();
+    text::write_tree(&data, &dir).unwrap();
+    fs::write(dir.join("package.json"), "{}\n").unwrap();
+    text::write_tree(&data, &dir).unwrap();
+    let kept = dir.join("package.json").exists();
+    let from_tree = text::read_tree(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(kept);
+    assert_eq!(from_tree.asanas.len(), data.asanas.len());
+}
+
+#[test]
+fn refuses_a_directory_that_isnt_a_tree() {
+    let dir = scratch_dir("not-a-tree");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("package.json"), "{}\n").unwrap();
+    let error = text::write_tree(&out_db(), &dir).unwrap_err();
+    let names = fs::read_dir(&dir).unwrap().count();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(error, text::TextError::NotATree(_)));
+    assert_eq!(names, 1);
+}
+
+#[test]
+fn refuses_two_asanas_with_one_pose() {
+    let dir = scratch_dir("duplicate-pose");
+    let mut data = common::two_asanas();
+    data.asanas[1].pose_id = 10;
+    text::write_tree(&data, &dir).unwrap();
+    let error = text::read_tree(&dir).unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let text::TextError::DuplicatePose { pose_id, first, second } = &error else {
+        panic!("{error}");
+    };
+    assert_eq!(*pose_id, 10);
+    assert_eq!(first.file_name().unwrap(), "152-tadasana.json");
+    assert_eq!(second.file_name().unwrap(), "170-virasana.json");
+    assert!(error.to_string().ends_with("170-virasana.json both have pose 10"), "{error}");
+}
+
+#[test]
+fn asana_without_a_pose_stays_without_one() {
+    let dir = scratch_dir("no-pose");
+    let mut data = common::two_asanas();
+    data.poses.remove(&20);
+    text::write_tree(&data, &dir).unwrap();
+    let from_tree = text::read_tree(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(from_tree, data);
+    assert!(!from_tree.poses.contains_key(&20));
+}
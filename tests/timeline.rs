@@ -0,0 +1,142 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::fs;
+
+use bevy::math::curve::EaseFunction;
+use common::{joints, out_db};
+use yogamat_wasm::{
+    skeleton::Joint,
+    timeline::{Keyframe, TimeTaken, Timeline, TimelineError},
+};
+
+fn key(time: f32, label: &str, joints: &[Joint]) -> Keyframe {
+    Keyframe {
+        time,
+        easing: EaseFunction::Linear,
+        label: label.to_string(),
+        joints: joints.to_vec(),
+    }
+}
+
+fn labels(timeline: &Timeline) -> Vec<(f32, &str)> {
+    timeline
+        .keyframes
+        .iter()
+        .map(|keyframe| (keyframe.time, keyframe.label.as_str()))
+        .collect()
+}
+
+#[test]
+fn inserts_in_time_order() {
+    let data = out_db();
+    let pose = joints(&data, "Tadasana");
+    let mut timeline = Timeline::default();
+    assert_eq!(timeline.insert(key(2.0, "b", pose)), 0);
+    assert_eq!(timeline.insert(key(0.0, "a", pose)), 0);
+    assert_eq!(timeline.insert(key(3.0, "c", pose)), 2);
+    // A key captured or inserted at the time of another takes its place.
+    assert_eq!(timeline.insert(key(2.0, "d", pose)), 1);
+    assert_eq!(labels(&timeline), [(0.0, "a"), (2.0, "d"), (3.0, "c")]);
+    assert_eq!(timeline.duration(), 3.0);
+}
+
+#[test]
+fn retiming_keeps_the_other_keys() {
+    let data = out_db();
+    let pose = joints(&data, "Tadasana");
+    let mut timeline = Timeline::default();
+    for (time, label) in [(0.0, "a"), (1.0, "b"), (2.0, "c")] {
+        timeline.insert(key(time, label, pose));
+    }
+    assert_eq!(timeline.retime(2, 0.5), Ok(1));
+    assert_eq!(labels(&timeline), [(0.0, "a"), (0.5, "c"), (1.0, "b")]);
+    // Dragged onto the first key, or before the start, it fails and stays
+    // where it was.
+    assert_eq!(timeline.retime(1, 0.0), Err(TimeTaken(0.0)));
+    assert_eq!(timeline.retime(2, -1.0), Err(TimeTaken(0.0)));
+    assert_eq!(labels(&timeline), [(0.0, "a"), (0.5, "c"), (1.0, "b")]);
+    // Retimed to where it already is, it's still there.
+    assert_eq!(timeline.retime(0, 0.0), Ok(0));
+    assert_eq!(timeline.retime(0, -1.0), Ok(0));
+    assert_eq!(timeline.retime(0, 3.0), Ok(2));
+    assert_eq!(labels(&timeline), [(0.5, "c"), (1.0, "b"), (3.0, "a")]);
+}
+
+/// Reads `keys`, JSON keyframes without joints, as a timeline file.
+fn read(name: &str, keys: &[&str]) -> Result<Timeline, TimelineError> {
+    let keys = keys
+        .iter()
+        .map(|key| format!(r#"{{{key}, "easing": "Linear", "label": "", "joints": []}}"#))
+        .collect::<Vec<String>>();
+    let path = std::env::temp_dir().join(format!("yogamat-timeline-{name}-{}.json", std::process::id()));
+    fs::write(&path, format!(r#"{{"keyframes": [{}]}}"#, keys.join(", "))).unwrap();
+    let timeline = Timeline::read(&path);
+    fs::remove_file(&path).unwrap();
+    timeline
+}
+
+#[test]
+fn reads_keys_in_time_order() {
+    let data = out_db();
+    let mut timeline = Timeline::default();
+    timeline.insert(key(0.0, "a", joints(&data, "Tadasana")));
+    timeline.insert(key(2.0, "b", joints(&data, "Uttanasana")));
+    let path = std::env::temp_dir().join(format!("yogamat-timeline-{}.json", std::process::id()));
+    timeline.write(&path).unwrap();
+    let read_back = Timeline::read(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(read_back.unwrap(), timeline);
+
+    let sorted = read("sorted", &[r#""time": 2.0"#, r#""time": 0.5"#, r#""time": 1.0"#]).unwrap();
+    assert_eq!(labels(&sorted).iter().map(|(time, _label)| *time).collect::<Vec<f32>>(), [0.5, 1.0, 2.0]);
+}
+
+#[test]
+fn rejects_bad_key_times() {
+    let error = read("duplicate", &[r#""time": 1.0"#, r#""time": 0.0"#, r#""time": 1.0"#]).unwrap_err();
+    assert!(matches!(error, TimelineError::DuplicateKeyTime(_, 1.0)), "{error}");
+    assert!(error.to_string().ends_with("more than one key is at 1 s"), "{error}");
+
+    // Too big for an f32.
+    let error = read("infinite", &[r#""time": 0.0"#, r#""time": 1e39"#]).unwrap_err();
+    assert!(matches!(error, TimelineError::BadKeyTime(_, time) if time == f32::INFINITY), "{error}");
+}
+
+#[test]
+fn samples_between_keys() {
+    let data = out_db();
+    let standing = joints(&data, "Tadasana");
+    let folded = joints(&data, "Uttanasana");
+    let mut timeline = Timeline::default();
+    assert_eq!(timeline.sample(0.0), None);
+    timeline.insert(key(1.0, "standing", standing));
+    timeline.insert(key(3.0, "folded", folded));
+
+    let at = |time| timeline.sample(time).unwrap();
+    let lands_on = |time, joints: &[Joint]| {
+        let sampled = at(time);
+        assert_eq!(sampled.len(), joints.len());
+        for ((joint_id, mat), joint) in sampled.iter().zip(joints) {
+            assert_eq!(*joint_id, joint.joint_id);
+            assert!(mat.abs_diff_eq(joint.matrix(), 1e-4), "joint {joint_id} is off at {time} s");
+        }
+    };
+    // Held before the first key and after the last.
+    lands_on(0.0, standing);
+    lands_on(1.0, standing);
+    lands_on(3.0, folded);
+    lands_on(5.0, folded);
+    // Halfway in time, each bone has turned halfway.
+    for (joint_id, mat) in at(2.0) {
+        let rotation = |joints: &[Joint]| {
+            let joint = joints.iter().find(|joint| joint.joint_id == joint_id).unwrap();
+            joint.matrix().to_scale_rotation_translation().1
+        };
+        let halfway = mat.to_scale_rotation_translation().1;
+        let turned = halfway.angle_between(rotation(standing));
+        let to_go = halfway.angle_between(rotation(folded));
+        assert!((turned - to_go).abs() < 1e-3, "joint {joint_id} turned {turned} with {to_go} to go");
+    }
+}
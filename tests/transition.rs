@@ -0,0 +1,81 @@
+use bevy::{math::curve::EaseFunction, prelude::*};
+use yogamat_wasm::transition::{interpolate, Transition};
+
+fn moves() -> Vec<(Entity, Transform, Transform)> {
+    vec![
+        (
+            Entity::from_raw(1),
+            Transform::IDENTITY,
+            Transform::from_xyz(10.0, 0.0, 0.0).with_rotation(Quat::from_rotation_y(1.0)),
+        ),
+        (Entity::from_raw(2), Transform::from_xyz(0.0, 4.0, 0.0), Transform::IDENTITY),
+    ]
+}
+
+#[test]
+fn progress_follows_the_clock() {
+    let mut transition = Transition::new(moves(), 2.0, EaseFunction::Linear);
+    assert_eq!(transition.progress(), 0.0);
+    transition.advance(0.5);
+    assert_eq!(transition.progress(), 0.25);
+    assert!(!transition.is_finished());
+    transition.advance(5.0);
+    assert_eq!(transition.progress(), 1.0);
+    assert!(transition.is_finished());
+
+    let instant = Transition::new(moves(), 0.0, EaseFunction::Linear);
+    assert!(instant.is_finished());
+}
+
+#[test]
+fn bones_move_along_the_easing_and_land_on_their_targets() {
+    let mut transition = Transition::new(moves(), 2.0, EaseFunction::SmoothStep);
+    let halfway = transition.advance(1.0);
+    let (entity, placed) = halfway[0];
+    assert_eq!(entity, Entity::from_raw(1));
+    assert!(placed.translation.abs_diff_eq(Vec3::new(5.0, 0.0, 0.0), 1e-5));
+    assert!(placed.rotation.abs_diff_eq(Quat::from_rotation_y(0.5), 1e-5));
+    // Smooth step starts slower than linear.
+    let mut eased = Transition::new(moves(), 2.0, EaseFunction::SmoothStep);
+    let early = eased.advance(0.4)[0].1.translation.x;
+    assert!(early > 0.0 && early < 2.0);
+
+    let landed = transition.advance(1.0);
+    for ((entity, placed), (moved, _from, to)) in landed.iter().zip(moves()) {
+        assert_eq!(*entity, moved);
+        assert_eq!(*placed, to);
+    }
+}
+
+#[test]
+fn an_interrupted_transition_starts_the_next_where_the_bones_are() {
+    let mut first = Transition::new(moves(), 1.0, EaseFunction::Linear);
+    let midway = first.advance(0.5);
+    let onwards = midway
+        .iter()
+        .map(|(entity, placed)| (*entity, *placed, Transform::from_xyz(0.0, -6.0, 0.0)))
+        .collect::<Vec<_>>();
+    let mut second = Transition::new(onwards, 1.0, EaseFunction::Linear);
+    let started = second.advance(0.0);
+    assert_eq!(started, midway);
+    let (_entity, placed) = second.advance(0.5)[1];
+    assert!(placed.translation.abs_diff_eq(interpolate(&midway[1].1, &Transform::from_xyz(0.0, -6.0, 0.0), 0.5).translation, 1e-5));
+    assert!(placed.translation.abs_diff_eq(Vec3::new(0.0, -2.0, 0.0), 1e-5));
+}
+
+#[test]
+fn replacing_moves_both_ends() {
+    let mut transition = Transition::new(moves(), 1.0, EaseFunction::Linear);
+    transition.replace(|_entity, transform| Transform {
+        translation: transform.translation * 2.0,
+        ..*transform
+    });
+    let landed = transition.advance(1.0);
+    assert_eq!(landed[0].1.translation, Vec3::new(20.0, 0.0, 0.0));
+    let mut transition = Transition::new(moves(), 1.0, EaseFunction::Linear);
+    transition.replace(|_entity, transform| Transform {
+        translation: transform.translation * 2.0,
+        ..*transform
+    });
+    assert_eq!(transition.advance(0.0)[1].1.translation, Vec3::new(0.0, 8.0, 0.0));
+}
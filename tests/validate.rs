@@ -0,0 +1,117 @@
+mod common;
+
+use bevy::math::Vec3;
+use common::{out_db, two_asanas};
+use yogamat_wasm::{
+    skeleton::Joint,
+    validate::{repair, validate, Axis, Problem},
+    AsanaData,
+};
+
+/// Asana id of Virasana in `two_asanas`.
+const VIRASANA: i32 = 170;
+
+fn problems(data: &AsanaData) -> Vec<(i32, Option<i32>, Problem)> {
+    validate(data)
+        .issues
+        .into_iter()
+        .map(|issue| (issue.asana_id, issue.joint_id, issue.problem))
+        .collect()
+}
+
+fn virasana_joint(data: &mut AsanaData, joint_id: i32) -> &mut Joint {
+    data.poses
+        .get_mut(&20)
+        .unwrap()
+        .iter_mut()
+        .find(|joint| joint.joint_id == joint_id)
+        .unwrap()
+}
+
+#[test]
+fn out_db_is_clean() {
+    let report = validate(&out_db());
+    assert!(report.is_clean(), "{}", report.issues[0]);
+}
+
+#[test]
+fn missing_joint_is_copied_from_tadasana() {
+    let mut data = two_asanas();
+    data.poses.get_mut(&20).unwrap().retain(|joint| joint.joint_id != 12);
+    assert_eq!(problems(&data), vec![(VIRASANA, Some(12), Problem::MissingJoint)]);
+
+    let repaired = repair(&mut data);
+    assert_eq!(repaired.len(), 1);
+    assert!(validate(&data).is_clean());
+    assert_eq!(data.poses[&20].len(), 40);
+    assert!(data.poses[&20].iter().all(|joint| joint.pose_id == 20));
+}
+
+#[test]
+fn renamed_tadasana_still_repairs() {
+    let mut data = two_asanas();
+    data.asanas[0].sanskrit = "Samasthiti".to_string();
+    data.poses.get_mut(&20).unwrap().retain(|joint| joint.joint_id != 12);
+
+    assert_eq!(repair(&mut data).len(), 1);
+    assert!(validate(&data).is_clean());
+}
+
+#[test]
+fn short_axes_are_accepted_within_tolerance() {
+    let mut data = two_asanas();
+    virasana_joint(&mut data, 5).up_y = 0.96;
+    assert!(validate(&data).is_clean());
+
+    virasana_joint(&mut data, 5).up_y = 0.9;
+    assert_eq!(
+        problems(&data),
+        vec![(VIRASANA, Some(5), Problem::NotUnitLength { axis: Axis::Up, length: 0.9 })]
+    );
+}
+
+#[test]
+fn drifted_axes_are_orthonormalized() {
+    let mut data = two_asanas();
+    let joint = virasana_joint(&mut data, 5);
+    (joint.up_x, joint.up_y, joint.up_z) = (0.0, 0.5, 0.0);
+    (joint.forward_x, joint.forward_y, joint.forward_z) = (0.0, 0.5, 1.0);
+    assert_eq!(problems(&data).len(), 3);
+
+    let repaired = repair(&mut data);
+    assert_eq!(repaired.len(), 3);
+    assert!(validate(&data).is_clean());
+    let joint = virasana_joint(&mut data, 5);
+    assert_eq!(Vec3::new(joint.up_x, joint.up_y, joint.up_z), Vec3::Y);
+    assert_eq!(Vec3::new(joint.forward_x, joint.forward_y, joint.forward_z), Vec3::Z);
+}
+
+#[test]
+fn degenerate_joint_is_replaced() {
+    let mut data = two_asanas();
+    let joint = virasana_joint(&mut data, 7);
+    joint.up_x = f32::NAN;
+    joint.origin_y = 3.0;
+    assert_eq!(problems(&data), vec![(VIRASANA, Some(7), Problem::NotFinite)]);
+
+    repair(&mut data);
+    assert!(validate(&data).is_clean());
+    assert_eq!(virasana_joint(&mut data, 7).origin_y, 0.0);
+}
+
+#[test]
+fn only_fixed_problems_are_reported_as_repaired() {
+    let mut data = two_asanas();
+    // Both problems are on the asana rather than a joint, only the unknown
+    // category can be repaired.
+    data.asanas.push(common::asana(VIRASANA, 20, "Virasana"));
+    data.asanas[1].category_ids.push(99);
+    let found = problems(&data);
+    assert!(found.contains(&(VIRASANA, None, Problem::UnknownCategory(99))));
+    assert!(found.contains(&(VIRASANA, None, Problem::DuplicateAsana)));
+
+    let repaired = repair(&mut data);
+    assert_eq!(repaired.len(), 1);
+    assert_eq!(repaired[0].problem, Problem::UnknownCategory(99));
+    assert_eq!(problems(&data), vec![(VIRASANA, None, Problem::DuplicateAsana)]);
+}